@@ -0,0 +1,59 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
+
+use crate::snapshot::{from_unix_ms, to_unix_ms};
+
+/// How often a cache key was served from the cache. The counters are kept by
+/// the [`crate::ApiCache`] rather than the store, so they work with every
+/// backend. Clones share the counters.
+#[derive(Debug, Clone, Default)]
+pub struct AccessStats(Arc<AccessCounters>);
+
+#[derive(Debug, Default)]
+struct AccessCounters {
+    hits: AtomicU64,
+    // Unix time of the last hit in milliseconds, 0 before the first hit
+    last_hit_ms: AtomicU64,
+    // Unix time in milliseconds until which the key's entry may be stored
+    retained_until_ms: AtomicU64,
+}
+
+impl AccessStats {
+    pub(crate) fn record_hit(&self) {
+        self.0.hits.fetch_add(1, Ordering::Relaxed);
+        self.0
+            .last_hit_ms
+            .store(to_unix_ms(SystemTime::now()), Ordering::Relaxed);
+    }
+
+    /// Keep the counters at least until `time`, when the entry they belong
+    /// to may leave the store.
+    pub(crate) fn retain_until(&self, time: SystemTime) {
+        self.0
+            .retained_until_ms
+            .fetch_max(to_unix_ms(time), Ordering::Relaxed);
+    }
+
+    /// Whether the entry may still be stored, so its counters are still needed.
+    pub(crate) fn is_retained(&self) -> bool {
+        self.0.retained_until_ms.load(Ordering::Relaxed) >= to_unix_ms(SystemTime::now())
+    }
+
+    pub fn hits(&self) -> u64 {
+        self.0.hits.load(Ordering::Relaxed)
+    }
+
+    pub fn last_hit(&self) -> Option<SystemTime> {
+        match self.0.last_hit_ms.load(Ordering::Relaxed) {
+            0 => None,
+            ms => Some(from_unix_ms(ms)),
+        }
+    }
+
+    /// Time since the last hit, `None` before the first hit.
+    pub fn since_last_hit(&self) -> Option<Duration> {
+        self.last_hit()
+            .map(|time| SystemTime::now().duration_since(time).unwrap_or_default())
+    }
+}
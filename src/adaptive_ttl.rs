@@ -0,0 +1,129 @@
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+
+/// Weight of the latest refresh in the change rate, so the rate follows
+/// roughly the last few refreshes.
+const CHANGE_RATE_WEIGHT: f64 = 0.3;
+
+/// Bounds of an adaptive TTL. The TTL of a key moves between `min`, when its
+/// body changes on every refresh, and `max`, when it never changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AdaptiveTtl {
+    pub min: Duration,
+    pub max: Duration,
+}
+
+impl AdaptiveTtl {
+    pub fn new(min_secs: u64, max_secs: u64) -> Self {
+        Self {
+            min: Duration::from_secs(min_secs),
+            max: Duration::from_secs(max_secs.max(min_secs)),
+        }
+    }
+
+    /// TTL for a key whose body changed on `change_rate` (0 to 1) of the
+    /// recent refreshes.
+    fn ttl_for(&self, change_rate: f64) -> Duration {
+        self.min + (self.max - self.min).mul_f64(1.0 - change_rate)
+    }
+
+    /// Change rate at which [`AdaptiveTtl::ttl_for`] returns `ttl`.
+    fn change_rate_for(&self, ttl: Duration) -> f64 {
+        let range = (self.max - self.min).as_secs_f64();
+        if range == 0.0 {
+            return 0.5;
+        }
+        let ttl = ttl.clamp(self.min, self.max);
+        1.0 - (ttl - self.min).as_secs_f64() / range
+    }
+}
+
+/// How often the body of one key changed across refreshes.
+#[derive(Debug, Clone)]
+pub(crate) struct ChangeTracker {
+    body_hash: u64,
+    change_rate: f64,
+    refreshes: u64,
+    changes: u64,
+    ttl: Duration,
+    updated_at: Instant,
+}
+
+impl ChangeTracker {
+    /// Tracker for a key first fetched with `body`, starting at `initial_ttl`.
+    pub(crate) fn new(
+        body: &str,
+        status_code: u16,
+        initial_ttl: Duration,
+        bounds: AdaptiveTtl,
+    ) -> Self {
+        let ttl = initial_ttl.clamp(bounds.min, bounds.max);
+        Self {
+            body_hash: body_hash(body, status_code),
+            change_rate: bounds.change_rate_for(ttl),
+            refreshes: 0,
+            changes: 0,
+            ttl,
+            updated_at: Instant::now(),
+        }
+    }
+
+    /// Record a refresh returning `body` and return the key's new TTL.
+    pub(crate) fn observe(
+        &mut self,
+        body: &str,
+        status_code: u16,
+        bounds: AdaptiveTtl,
+    ) -> Duration {
+        let hash = body_hash(body, status_code);
+        let changed = hash != self.body_hash;
+        self.body_hash = hash;
+        self.refreshes += 1;
+        if changed {
+            self.changes += 1;
+        }
+        let sample = if changed { 1.0 } else { 0.0 };
+        self.change_rate += CHANGE_RATE_WEIGHT * (sample - self.change_rate);
+        self.ttl = bounds.ttl_for(self.change_rate);
+        self.updated_at = Instant::now();
+        self.ttl
+    }
+
+    pub(crate) fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    pub(crate) fn idle_for(&self) -> Duration {
+        self.updated_at.elapsed()
+    }
+
+    pub(crate) fn status(&self, key: &str) -> AdaptiveTtlStatus {
+        AdaptiveTtlStatus {
+            key: key.to_string(),
+            ttl_secs: self.ttl.as_secs_f64(),
+            change_rate: self.change_rate,
+            refreshes: self.refreshes,
+            changes: self.changes,
+        }
+    }
+}
+
+/// Effective TTL of one key, for the status endpoint.
+#[derive(Debug, Clone, Serialize)]
+pub struct AdaptiveTtlStatus {
+    pub key: String,
+    pub ttl_secs: f64,
+    /// Weighted share of recent refreshes that returned a different body.
+    pub change_rate: f64,
+    pub refreshes: u64,
+    pub changes: u64,
+}
+
+fn body_hash(body: &str, status_code: u16) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    status_code.hash(&mut hasher);
+    body.hash(&mut hasher);
+    hasher.finish()
+}
@@ -0,0 +1,206 @@
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Settings shared by every circuit breaker of an [`crate::ApiCache`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failures that open the circuit. Zero disables the breaker.
+    pub failure_threshold: u32,
+    /// How long the circuit stays open before a trial request is let through.
+    pub cool_down: Duration,
+}
+
+impl CircuitBreakerConfig {
+    pub fn new(failure_threshold: u32, cool_down_secs: u64) -> Self {
+        Self {
+            failure_threshold,
+            cool_down: Duration::from_secs(cool_down_secs),
+        }
+    }
+
+    pub fn disabled() -> Self {
+        Self::new(0, 0)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.failure_threshold > 0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakerState {
+    /// Requests flow normally.
+    Closed,
+    /// Requests fail fast until the cool-down has passed.
+    Open,
+    /// A single trial request decides whether to close or reopen the circuit.
+    HalfOpen,
+}
+
+/// Snapshot of a breaker for logs and the status endpoint.
+#[derive(Debug, Clone, Serialize)]
+pub struct BreakerStatus {
+    pub upstream: String,
+    pub state: BreakerState,
+    pub consecutive_failures: u32,
+    pub failure_threshold: u32,
+    /// Seconds until a trial request is allowed, while open.
+    pub retry_in_secs: Option<u64>,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug)]
+struct BreakerInner {
+    state: BreakerState,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    trial_in_flight: bool,
+    last_error: Option<String>,
+}
+
+/// Circuit breaker guarding one upstream host.
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    upstream: String,
+    config: CircuitBreakerConfig,
+    inner: Arc<Mutex<BreakerInner>>,
+}
+
+impl CircuitBreaker {
+    pub fn new(upstream: &str, config: CircuitBreakerConfig) -> Self {
+        Self {
+            upstream: upstream.to_string(),
+            config,
+            inner: Arc::new(Mutex::new(BreakerInner {
+                state: BreakerState::Closed,
+                consecutive_failures: 0,
+                opened_at: None,
+                trial_in_flight: false,
+                last_error: None,
+            })),
+        }
+    }
+
+    pub fn state(&self) -> BreakerState {
+        self.inner.lock().unwrap().state
+    }
+
+    /// Ask to send a request upstream. Fails fast while the circuit is open
+    /// or while the half-open trial request is still running.
+    pub fn try_acquire(&self) -> Result<(), String> {
+        if !self.config.is_enabled() {
+            return Ok(());
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+        match inner.state {
+            BreakerState::Closed => Ok(()),
+            BreakerState::Open => {
+                let opened_at = inner.opened_at.unwrap_or_else(Instant::now);
+                if opened_at.elapsed() >= self.config.cool_down {
+                    info!(
+                        "Circuit breaker for {} is half-open, sending trial request",
+                        self.upstream
+                    );
+                    inner.state = BreakerState::HalfOpen;
+                    inner.trial_in_flight = true;
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "Circuit breaker open for {}, retry in {}s",
+                        self.upstream,
+                        self.config
+                            .cool_down
+                            .saturating_sub(opened_at.elapsed())
+                            .as_secs()
+                    ))
+                }
+            }
+            BreakerState::HalfOpen => {
+                if inner.trial_in_flight {
+                    Err(format!(
+                        "Circuit breaker half-open for {}, trial request in progress",
+                        self.upstream
+                    ))
+                } else {
+                    inner.trial_in_flight = true;
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    /// Give back a request allowed by [`CircuitBreaker::try_acquire`] that
+    /// was never sent, so a half-open trial can still be made.
+    pub fn release(&self) {
+        self.inner.lock().unwrap().trial_in_flight = false;
+    }
+
+    pub fn record_success(&self) {
+        if !self.config.is_enabled() {
+            return;
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+        if inner.state != BreakerState::Closed {
+            info!(
+                "Circuit breaker for {} closed, upstream recovered",
+                self.upstream
+            );
+        }
+        inner.state = BreakerState::Closed;
+        inner.consecutive_failures = 0;
+        inner.opened_at = None;
+        inner.trial_in_flight = false;
+    }
+
+    pub fn record_failure(&self, error: &str) {
+        if !self.config.is_enabled() {
+            return;
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+        inner.consecutive_failures = inner.consecutive_failures.saturating_add(1);
+        inner.last_error = Some(error.to_string());
+        inner.trial_in_flight = false;
+
+        let should_open = match inner.state {
+            BreakerState::Closed => inner.consecutive_failures >= self.config.failure_threshold,
+            BreakerState::HalfOpen => true,
+            BreakerState::Open => false,
+        };
+        if should_open {
+            warn!(
+                "Circuit breaker for {} opened after {} consecutive failures, last error: {}",
+                self.upstream, inner.consecutive_failures, error
+            );
+            inner.state = BreakerState::Open;
+            inner.opened_at = Some(Instant::now());
+        }
+    }
+
+    pub fn status(&self) -> BreakerStatus {
+        let inner = self.inner.lock().unwrap();
+        let retry_in_secs = match (inner.state, inner.opened_at) {
+            (BreakerState::Open, Some(opened_at)) => Some(
+                self.config
+                    .cool_down
+                    .saturating_sub(opened_at.elapsed())
+                    .as_secs(),
+            ),
+            _ => None,
+        };
+
+        BreakerStatus {
+            upstream: self.upstream.clone(),
+            state: inner.state,
+            consecutive_failures: inner.consecutive_failures,
+            failure_threshold: self.config.failure_threshold,
+            retry_in_secs,
+            last_error: inner.last_error.clone(),
+        }
+    }
+}
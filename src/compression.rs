@@ -0,0 +1,169 @@
+use bytes::Bytes;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+
+/// Bodies smaller than this are sent as they are; compressing them saves
+/// less than the encoding overhead.
+const MIN_COMPRESS_BYTES: usize = 1024;
+
+/// Brotli quality and window size. Bodies are compressed once per refresh,
+/// so a high quality is affordable.
+const BROTLI_QUALITY: u32 = 9;
+const BROTLI_WINDOW: u32 = 22;
+
+/// A content coding the cache can serve a body in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentEncoding {
+    Identity,
+    Gzip,
+    Brotli,
+}
+
+impl ContentEncoding {
+    /// Token used in `Accept-Encoding` and `Content-Encoding`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentEncoding::Identity => "identity",
+            ContentEncoding::Gzip => "gzip",
+            ContentEncoding::Brotli => "br",
+        }
+    }
+
+    /// ETag of this encoding of a body whose ETag is `etag`. Encoded bodies
+    /// are different representations and need their own strong ETag.
+    pub fn tag_etag(&self, etag: &str) -> String {
+        match self {
+            ContentEncoding::Identity => etag.to_string(),
+            _ => match etag.strip_suffix('"') {
+                Some(opaque) => format!("{}-{}\"", opaque, self.as_str()),
+                None => format!("{}-{}", etag, self.as_str()),
+            },
+        }
+    }
+}
+
+/// Gzip and brotli encodings of a cached body, each kept only when it is
+/// smaller than the body itself.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CompressedBodies {
+    #[serde(default, with = "base64_body")]
+    gzip: Option<Bytes>,
+    #[serde(default, with = "base64_body")]
+    brotli: Option<Bytes>,
+}
+
+impl CompressedBodies {
+    /// Compress `body` with every supported encoding.
+    pub fn compress(body: &str) -> Self {
+        let body = body.as_bytes();
+        if body.len() < MIN_COMPRESS_BYTES {
+            return Self::default();
+        }
+        let smaller = |encoded: Vec<u8>| (encoded.len() < body.len()).then(|| Bytes::from(encoded));
+        Self {
+            gzip: gzip(body).and_then(smaller),
+            brotli: brotli(body).and_then(smaller),
+        }
+    }
+
+    /// The body in `encoding`, `None` for identity or when that encoding is
+    /// not available.
+    pub fn get(&self, encoding: ContentEncoding) -> Option<&Bytes> {
+        match encoding {
+            ContentEncoding::Identity => None,
+            ContentEncoding::Gzip => self.gzip.as_ref(),
+            ContentEncoding::Brotli => self.brotli.as_ref(),
+        }
+    }
+
+    /// Best available encoding accepted by an `Accept-Encoding` header,
+    /// preferring brotli over gzip when both are equally acceptable.
+    pub fn negotiate(&self, accept_encoding: &str) -> ContentEncoding {
+        let mut best = ContentEncoding::Identity;
+        let mut best_quality = 0.0;
+        for encoding in [ContentEncoding::Brotli, ContentEncoding::Gzip] {
+            if self.get(encoding).is_none() {
+                continue;
+            }
+            let quality = accepted_quality(accept_encoding, encoding.as_str());
+            if quality > best_quality {
+                best = encoding;
+                best_quality = quality;
+            }
+        }
+        best
+    }
+
+    /// Size of all encodings in bytes.
+    pub fn size_bytes(&self) -> usize {
+        self.gzip.as_ref().map_or(0, |body| body.len())
+            + self.brotli.as_ref().map_or(0, |body| body.len())
+    }
+}
+
+/// Quality value `Accept-Encoding` gives `coding`, falling back to the
+/// value of `*` and to 0 when neither is listed.
+fn accepted_quality(accept_encoding: &str, coding: &str) -> f32 {
+    let mut wildcard = None;
+    for item in accept_encoding.split(',') {
+        let mut parts = item.split(';');
+        let name = parts.next().unwrap_or("").trim();
+        let quality = parts
+            .filter_map(|param| param.trim().strip_prefix("q="))
+            .find_map(|q| q.trim().parse::<f32>().ok())
+            .unwrap_or(1.0);
+        if name.eq_ignore_ascii_case(coding) {
+            return quality;
+        }
+        if name == "*" {
+            wildcard = Some(quality);
+        }
+    }
+    wildcard.unwrap_or(0.0)
+}
+
+fn gzip(body: &[u8]) -> Option<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(body).ok()?;
+    encoder.finish().ok()
+}
+
+fn brotli(body: &[u8]) -> Option<Vec<u8>> {
+    let mut encoder =
+        brotli::CompressorWriter::new(Vec::new(), 4096, BROTLI_QUALITY, BROTLI_WINDOW);
+    encoder.write_all(body).ok()?;
+    Some(encoder.into_inner())
+}
+
+/// Encoded bodies as base64 strings, so stores that keep JSON stay compact.
+mod base64_body {
+    use base64::Engine;
+    use base64::engine::general_purpose::STANDARD;
+    use bytes::Bytes;
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    pub fn serialize<S: Serializer>(
+        body: &Option<Bytes>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match body {
+            Some(body) => serializer.serialize_some(&STANDARD.encode(body)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Bytes>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(|encoded| {
+                STANDARD
+                    .decode(encoded)
+                    .map(Bytes::from)
+                    .map_err(D::Error::custom)
+            })
+            .transpose()
+    }
+}
@@ -0,0 +1,545 @@
+use bytes::Bytes;
+use salvo::affix_state;
+use salvo::http::Method;
+use salvo::http::header::{self, HeaderValue};
+use salvo::prelude::*;
+use salvo::serve_static::StaticDir;
+use std::sync::Arc;
+use std::time::SystemTime;
+use tracing::error;
+
+use crate::{
+    ApiCache, CacheLookup, CacheStatus, Config, MapsConfig, PopularQueries, RepoVersion,
+    ServerQuery, ServerSnapshot, ServerSnapshots, VersionInfo, canonical_query, etag_for,
+    find_online_players, get_latest_tag, parse_player_name,
+};
+
+/// Whether a proxied response was a cache `HIT`, `MISS` or `STALE`.
+const X_CACHE: &str = "x-cache";
+
+/// Shared state injected into the handlers.
+#[derive(Clone)]
+pub struct AppState {
+    pub config: Arc<Config>,
+    pub cache: Arc<ApiCache>,
+    pub maps_config: Arc<MapsConfig>,
+    /// Request counts of `/api/server_list` queries, read by the poller.
+    pub popular_server_queries: Arc<PopularQueries>,
+    /// Merged server list served by `/api/servers`.
+    pub server_snapshots: Arc<ServerSnapshots>,
+}
+
+#[handler]
+async fn ping() -> &'static str {
+    "pong"
+}
+
+/// Whether the copy the client already has is current, judged by the
+/// request's `If-None-Match` or, without it, `If-Modified-Since` header.
+fn is_not_modified(req: &Request, etag: &str, last_modified: SystemTime) -> bool {
+    if !matches!(*req.method(), Method::GET | Method::HEAD) {
+        return false;
+    }
+    let headers = req.headers();
+    if let Some(if_none_match) = headers.get(header::IF_NONE_MATCH) {
+        // If-None-Match uses weak comparison, so W/ prefixes are ignored
+        return if_none_match.to_str().is_ok_and(|tags| {
+            tags.split(',')
+                .map(str::trim)
+                .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+        });
+    }
+    headers
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|since| since.to_str().ok())
+        .and_then(|since| httpdate::parse_http_date(since).ok())
+        // HTTP dates have whole seconds, so compare at that precision
+        .is_some_and(|since| SystemTime::from(httpdate::HttpDate::from(last_modified)) <= since)
+}
+
+/// Set the validators of a response and answer 304 Not Modified when the
+/// client's copy is current. Returns whether the body should still be sent.
+fn check_validators(
+    req: &Request,
+    res: &mut Response,
+    etag: &str,
+    last_modified: SystemTime,
+) -> bool {
+    let headers = res.headers_mut();
+    if let Ok(value) = HeaderValue::from_str(etag) {
+        headers.insert(header::ETAG, value);
+    }
+    if let Ok(value) = HeaderValue::from_str(&httpdate::fmt_http_date(last_modified)) {
+        headers.insert(header::LAST_MODIFIED, value);
+    }
+    if is_not_modified(req, etag, last_modified) {
+        res.status_code(StatusCode::NOT_MODIFIED);
+        return false;
+    }
+    true
+}
+
+#[handler]
+async fn maps_handler(req: &mut Request, depot: &mut Depot, res: &mut Response) {
+    let maps_config = depot.obtain::<Arc<MapsConfig>>().unwrap();
+    let maps = maps_config.get_maps();
+
+    let body = match serde_json::to_vec(&maps) {
+        Ok(body) => body,
+        Err(e) => {
+            error!("Failed to serialize maps: {}", e);
+            res.status_code(StatusCode::INTERNAL_SERVER_ERROR);
+            return;
+        }
+    };
+    if check_validators(req, res, &etag_for(&body), maps_config.loaded_at) {
+        res.render(Json(&maps));
+    }
+}
+
+#[handler]
+async fn version_handler(depot: &mut Depot, res: &mut Response) {
+    let config = depot.obtain::<Arc<Config>>().unwrap();
+    let cache = depot.obtain::<Arc<ApiCache>>().unwrap();
+    let policy = config.version_policy();
+
+    let android_version = if let Some(ref android_repo_url) = config.android_repo_url {
+        match get_latest_tag(cache, android_repo_url, policy).await {
+            Some((version, url)) => RepoVersion {
+                version: Some(version),
+                url: Some(url),
+            },
+            None => RepoVersion {
+                version: None,
+                url: None,
+            },
+        }
+    } else {
+        RepoVersion {
+            version: None,
+            url: None,
+        }
+    };
+
+    let web_version = if let Some(ref web_repo_url) = config.web_repo_url {
+        match get_latest_tag(cache, web_repo_url, policy).await {
+            Some((version, url)) => RepoVersion {
+                version: Some(version),
+                url: Some(url),
+            },
+            None => RepoVersion {
+                version: None,
+                url: None,
+            },
+        }
+    } else {
+        RepoVersion {
+            version: None,
+            url: None,
+        }
+    };
+
+    let version_info = VersionInfo {
+        android: android_version,
+        web: web_version,
+    };
+
+    res.render(Json(&version_info));
+}
+
+/// Render a cached upstream response with its cache metadata. Successful
+/// responses are sent pre-compressed when the client accepts it and answer
+/// conditional requests.
+fn render_cached(req: &Request, res: &mut Response, lookup: CacheLookup) {
+    let status_code = StatusCode::from_u16(lookup.status_code).unwrap_or(StatusCode::OK);
+    res.status_code(status_code);
+    set_cache_headers(res, &lookup);
+    if !status_code.is_success() {
+        render_html(res, lookup.data.to_bytes());
+        return;
+    }
+
+    // The encoding is picked per request, so shared caches must key on it
+    res.headers_mut()
+        .insert(header::VARY, HeaderValue::from_static("accept-encoding"));
+    let accept_encoding = req
+        .headers()
+        .get(header::ACCEPT_ENCODING)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("");
+    let encoding = lookup.encodings.negotiate(accept_encoding);
+    if !check_validators(
+        req,
+        res,
+        &encoding.tag_etag(&lookup.etag),
+        lookup.last_modified,
+    ) {
+        return;
+    }
+    match lookup.encodings.get(encoding) {
+        Some(body) => {
+            res.headers_mut().insert(
+                header::CONTENT_ENCODING,
+                HeaderValue::from_static(encoding.as_str()),
+            );
+            render_html(res, body.clone());
+        }
+        None => render_html(res, lookup.data.to_bytes()),
+    }
+}
+
+/// Set `Age`, `Cache-Control` and `X-Cache` from a cache lookup, marking
+/// stale-if-error fallbacks with a `Warning`.
+fn set_cache_headers(res: &mut Response, lookup: &CacheLookup) {
+    let headers = res.headers_mut();
+    headers.insert(header::AGE, HeaderValue::from(lookup.age.as_secs()));
+    if !lookup.stored {
+        headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    } else if let Ok(value) = HeaderValue::from_str(&format!(
+        "public, max-age={}",
+        lookup.remaining_ttl().as_secs()
+    )) {
+        headers.insert(header::CACHE_CONTROL, value);
+    }
+    headers.insert(
+        X_CACHE,
+        HeaderValue::from_static(lookup.status.as_header_value()),
+    );
+    if lookup.status == CacheStatus::StaleIfError {
+        headers.insert(
+            header::WARNING,
+            HeaderValue::from_static("111 - \"Revalidation Failed\""),
+        );
+    }
+}
+
+/// Send a shared HTML body without copying it.
+fn render_html(res: &mut Response, body: Bytes) {
+    res.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("text/html; charset=utf-8"),
+    );
+    res.body(body);
+}
+
+/// Health of the cache and of every upstream origin, so an unhealthy
+/// upstream can be told apart from a problem in this server.
+#[handler]
+async fn status_handler(depot: &mut Depot, res: &mut Response) {
+    let cache = depot.obtain::<Arc<ApiCache>>().unwrap();
+    let stats = cache.stats().await;
+
+    res.render(Json(serde_json::json!({
+        "cache": {
+            "entries": stats.entries,
+            "size_bytes": stats.size_bytes,
+        },
+        "upstreams": cache.upstream_status(),
+        "rate_limit": cache.rate_limit_status(),
+        "adaptive_ttl": cache.adaptive_ttl_status(),
+    })));
+}
+
+#[handler]
+async fn servers_handler(req: &mut Request, depot: &mut Depot, res: &mut Response) {
+    // Get cache and route from depot
+    let cache = depot.obtain::<Arc<ApiCache>>().unwrap();
+    let route = depot.obtain::<Arc<Config>>().unwrap().server_list_route();
+
+    // Count requests per canonical query so the poller can keep popular pages warm
+    let query_string = canonical_query(req.uri().query().unwrap_or(""), route.params);
+    depot
+        .obtain::<Arc<PopularQueries>>()
+        .unwrap()
+        .record(&query_string);
+
+    match cache.lookup_route(&route, &query_string).await {
+        Ok(lookup) => render_cached(req, res, lookup),
+        Err(e) => {
+            error!("Failed to get server list: {}", e);
+            res.status_code(StatusCode::INTERNAL_SERVER_ERROR);
+            res.render(Text::Plain(format!("Unable to fetch server list: {}", e)));
+        }
+    }
+}
+
+/// The current server list snapshot, or `None` after rendering a 502 when it
+/// cannot be built.
+async fn current_snapshot(depot: &mut Depot, res: &mut Response) -> Option<Arc<ServerSnapshot>> {
+    let snapshots = depot.obtain::<Arc<ServerSnapshots>>().unwrap();
+    match snapshots.current().await {
+        Ok(snapshot) => Some(snapshot),
+        Err(e) => {
+            error!("Failed to build server list: {}", e);
+            res.status_code(StatusCode::BAD_GATEWAY);
+            res.render(Json(serde_json::json!({
+                "error": format!("Unable to fetch server list: {}", e),
+            })));
+            None
+        }
+    }
+}
+
+/// Render `body` with the snapshot's id and fetch time, cacheable until the
+/// snapshot expires.
+fn render_snapshot_json(
+    req: &Request,
+    res: &mut Response,
+    snapshot: &ServerSnapshot,
+    mut body: serde_json::Value,
+) {
+    if let Ok(value) = HeaderValue::from_str(&format!(
+        "public, max-age={}",
+        snapshot.remaining_ttl().as_secs()
+    )) {
+        res.headers_mut().insert(header::CACHE_CONTROL, value);
+    }
+    body["snapshot_id"] = snapshot.id.clone().into();
+    body["fetched_at"] = snapshot
+        .fetched_at
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        .into();
+    if check_validators(
+        req,
+        res,
+        &etag_for(body.to_string().as_bytes()),
+        snapshot.fetched_at,
+    ) {
+        res.render(Json(body));
+    }
+}
+
+/// Servers of the merged server list snapshot, filtered, sorted and paged
+/// as requested. Malformed servers are left out and listed under `skipped`.
+#[handler]
+async fn parsed_servers_handler(req: &mut Request, depot: &mut Depot, res: &mut Response) {
+    let query = match ServerQuery::parse(req.uri().query().unwrap_or("")) {
+        Ok(query) => query,
+        Err(e) => {
+            res.status_code(StatusCode::BAD_REQUEST);
+            res.render(Json(&e));
+            return;
+        }
+    };
+    let Some(snapshot) = current_snapshot(depot, res).await else {
+        return;
+    };
+
+    let page = query.apply(&snapshot.servers);
+    let body = serde_json::json!({
+        "pages": snapshot.pages,
+        "total": page.total,
+        "count": page.servers.len(),
+        "servers": page.servers,
+        "next_cursor": page.next_cursor,
+        "skipped": snapshot.skipped,
+    });
+    render_snapshot_json(req, res, &snapshot, body);
+}
+
+/// Servers of the snapshot on which a player with the requested name, or a
+/// name starting with it, is playing.
+#[handler]
+async fn online_players_handler(req: &mut Request, depot: &mut Depot, res: &mut Response) {
+    let name = req.query::<String>("name");
+    let name = match parse_player_name(name.as_deref()) {
+        Ok(name) => name,
+        Err(e) => {
+            res.status_code(StatusCode::BAD_REQUEST);
+            res.render(Json(serde_json::json!({ "error": e })));
+            return;
+        }
+    };
+    let Some(snapshot) = current_snapshot(depot, res).await else {
+        return;
+    };
+
+    let servers = find_online_players(&snapshot.servers, name);
+    let body = serde_json::json!({
+        "name": name,
+        "count": servers.len(),
+        "servers": servers,
+    });
+    render_snapshot_json(req, res, &snapshot, body);
+}
+
+#[handler]
+async fn players_handler(req: &mut Request, depot: &mut Depot, res: &mut Response) {
+    // Get cache and route from depot
+    let cache = depot.obtain::<Arc<ApiCache>>().unwrap();
+    let route = depot.obtain::<Arc<Config>>().unwrap().player_list_route();
+
+    let query_string = req.uri().query().unwrap_or("");
+    match cache.lookup_route(&route, query_string).await {
+        Ok(lookup) => render_cached(req, res, lookup),
+        Err(e) => {
+            error!("Failed to get players data: {}", e);
+            res.status_code(StatusCode::INTERNAL_SERVER_ERROR);
+            res.render(Text::Plain(format!("Unable to fetch players data: {}", e)));
+        }
+    }
+}
+
+/// Compare two byte strings in time independent of where they differ, so
+/// the admin token cannot be guessed byte by byte from response times.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Let admin requests through only with the configured bearer token. The
+/// admin API is unavailable while no token is configured.
+#[handler]
+async fn admin_auth(req: &mut Request, depot: &mut Depot, res: &mut Response, ctrl: &mut FlowCtrl) {
+    let config = depot.obtain::<Arc<Config>>().unwrap();
+    let Some(ref token) = config.admin_token else {
+        res.status_code(StatusCode::NOT_FOUND);
+        res.render(Json(
+            serde_json::json!({ "error": "admin API is disabled" }),
+        ));
+        ctrl.skip_rest();
+        return;
+    };
+
+    let authorized = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|given| constant_time_eq(given.trim().as_bytes(), token.as_bytes()));
+    if !authorized {
+        res.status_code(StatusCode::UNAUTHORIZED);
+        res.headers_mut()
+            .insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+        res.render(Json(
+            serde_json::json!({ "error": "invalid or missing admin token" }),
+        ));
+        ctrl.skip_rest();
+    }
+}
+
+/// List cached entries, optionally only keys starting with `?prefix=`.
+#[handler]
+async fn admin_cache_list(req: &mut Request, depot: &mut Depot, res: &mut Response) {
+    let cache = depot.obtain::<Arc<ApiCache>>().unwrap();
+    let prefix = req.query::<String>("prefix").unwrap_or_default();
+
+    match cache.entries(&prefix).await {
+        Ok(entries) => res.render(Json(serde_json::json!({
+            "count": entries.len(),
+            "entries": entries,
+        }))),
+        Err(e) => {
+            error!("Failed to list cache entries: {}", e);
+            res.status_code(StatusCode::INTERNAL_SERVER_ERROR);
+            res.render(Json(serde_json::json!({ "error": e })));
+        }
+    }
+}
+
+/// Purge the key given as `?key=`, every key starting with `?prefix=`, or
+/// the whole cache with `?all=true`. Anything else is rejected, so a typo
+/// cannot empty the cache.
+#[handler]
+async fn admin_cache_purge(req: &mut Request, depot: &mut Depot, res: &mut Response) {
+    let cache = depot.obtain::<Arc<ApiCache>>().unwrap();
+
+    if let Some(name) = req
+        .queries()
+        .keys()
+        .find(|name| !matches!(name.as_str(), "key" | "prefix" | "all"))
+    {
+        res.status_code(StatusCode::BAD_REQUEST);
+        res.render(Json(serde_json::json!({
+            "error": format!("unknown parameter '{}'", name)
+        })));
+        return;
+    }
+    let key = req.query::<String>("key");
+    let prefix = req.query::<String>("prefix");
+    let all = req.query::<String>("all");
+    let purged = match (key, prefix, all.as_deref()) {
+        (Some(key), None, None) => cache.purge(&key).await.map(usize::from),
+        (None, Some(prefix), None) if !prefix.is_empty() => cache.purge_prefix(&prefix).await,
+        (None, None, Some("true")) => cache.purge_prefix("").await,
+        _ => {
+            res.status_code(StatusCode::BAD_REQUEST);
+            res.render(Json(serde_json::json!({
+                "error": "expected one of key, a non-empty prefix or all=true"
+            })));
+            return;
+        }
+    };
+    match purged {
+        Ok(purged) => res.render(Json(serde_json::json!({ "purged": purged }))),
+        Err(e) => {
+            error!("Failed to purge cache: {}", e);
+            res.status_code(StatusCode::INTERNAL_SERVER_ERROR);
+            res.render(Json(serde_json::json!({ "error": e })));
+        }
+    }
+}
+
+/// Build the router serving the API and the static frontend.
+pub fn create_router(state: &AppState) -> Router {
+    Router::new()
+        .hoop(RequestId::new())
+        .push(Router::new().path("/ping").get(ping))
+        .push(
+            Router::new()
+                .path("/api/version")
+                .hoop(affix_state::inject(state.config.clone()))
+                .hoop(affix_state::inject(state.cache.clone()))
+                .get(version_handler),
+        )
+        .push(
+            Router::new()
+                .path("/api/maps")
+                .hoop(affix_state::inject(state.maps_config.clone()))
+                .get(maps_handler),
+        )
+        .push(
+            Router::new()
+                .path("/api/status")
+                .hoop(affix_state::inject(state.cache.clone()))
+                .get(status_handler),
+        )
+        .push(
+            Router::new()
+                .path("/api/server_list")
+                .hoop(affix_state::inject(state.cache.clone()))
+                .hoop(affix_state::inject(state.config.clone()))
+                .hoop(affix_state::inject(state.popular_server_queries.clone()))
+                .goal(servers_handler),
+        )
+        .push(
+            Router::new()
+                .path("/api/servers")
+                .hoop(affix_state::inject(state.server_snapshots.clone()))
+                .get(parsed_servers_handler),
+        )
+        .push(
+            Router::new()
+                .path("/api/players/online")
+                .hoop(affix_state::inject(state.server_snapshots.clone()))
+                .get(online_players_handler),
+        )
+        .push(
+            Router::new()
+                .path("/api/player_list")
+                .hoop(affix_state::inject(state.cache.clone()))
+                .hoop(affix_state::inject(state.config.clone()))
+                .goal(players_handler),
+        )
+        .push(
+            Router::new()
+                .path("/api/admin/cache")
+                .hoop(affix_state::inject(state.config.clone()))
+                .hoop(affix_state::inject(state.cache.clone()))
+                .hoop(admin_auth)
+                .get(admin_cache_list)
+                .delete(admin_cache_purge),
+        )
+        .push(Router::with_path("{**path}").get(StaticDir::new(["static"]).defaults("index.html")))
+}
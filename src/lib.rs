@@ -1,14 +1,61 @@
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::env;
-use std::sync::Arc;
-use std::time::{Duration, Instant};
-use tokio::sync::RwLock;
-use tracing::{error, info};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime};
+use tokio::sync::{broadcast, watch};
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+pub mod access_stats;
+pub mod adaptive_ttl;
+pub mod body;
+pub mod circuit_breaker;
+pub mod compression;
+pub mod handlers;
+pub mod player_search;
+pub mod poller;
+pub mod query;
+pub mod rate_limit;
+pub mod search;
+pub mod server_filter;
+pub mod server_list;
+pub mod server_snapshot;
+pub mod snapshot;
+pub mod store;
+pub mod upstream;
+
+pub use access_stats::AccessStats;
+pub use adaptive_ttl::{AdaptiveTtl, AdaptiveTtlStatus};
+pub use body::SharedBody;
+pub use circuit_breaker::{BreakerState, BreakerStatus, CircuitBreaker, CircuitBreakerConfig};
+pub use compression::{CompressedBodies, ContentEncoding};
+pub use player_search::{
+    OnlinePlayerServer, PlayerMatch, PlayerMatchKind, find_online_players, parse_player_name,
+};
+pub use poller::{CachePoller, PollerConfig, PopularQueries};
+pub use query::{
+    PLAYER_LIST_PARAMS, ParamKind, QueryParam, SERVER_LIST_PARAMS, build_url, canonical_query,
+};
+pub use rate_limit::{RateLimitConfig, RateLimitStatus, RateLimiter, UpstreamPermit};
+pub use search::{QueryError, SearchQuery};
+pub use server_filter::{ServerFilter, ServerPage, ServerQuery, ServerSort};
+pub use server_list::{GameServer, ParsedServerList, SkippedServer, parse_server_list};
+pub use server_snapshot::{ServerSnapshot, ServerSnapshotConfig, ServerSnapshots};
+pub use snapshot::{CacheSnapshot, SnapshotEntry};
+pub use store::{CacheStore, DiskStore, MemoryStore, RedisStore, StoreStats};
+pub use upstream::{UpstreamClient, UpstreamConfig};
+
+use adaptive_ttl::ChangeTracker;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MapsConfig {
     pub maps: Vec<MapEntry>,
+    /// When the configuration was loaded, sent as `Last-Modified` by `/api/maps`.
+    #[serde(skip, default = "SystemTime::now")]
+    pub loaded_at: SystemTime,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,7 +73,10 @@ impl Default for MapsConfig {
 
 impl MapsConfig {
     pub fn new() -> Self {
-        Self { maps: Vec::new() }
+        Self {
+            maps: Vec::new(),
+            loaded_at: SystemTime::now(),
+        }
     }
 
     pub async fn load_from_file(file_path: &str) -> Result<Self, String> {
@@ -95,24 +145,313 @@ pub struct RepoVersion {
     pub url: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+/// A cached upstream response, or the outcome of a failed request, as kept
+/// by a [`CacheStore`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CachedResponse {
-    data: String,
-    timestamp: Instant,
+    data: SharedBody,
+    // Wall-clock fetch time, so entries can be shared between processes
+    fetched_at: SystemTime,
     status_code: u16,
+    // Set when the entry records a failed request instead of a response
+    error: Option<String>,
+    // How long this entry is fresh, errors use the negative TTL of their route
+    ttl: Duration,
+    // Last time the upstream failed while this (successful) entry was kept
+    failed_at: Option<SystemTime>,
+    // Loaded from a snapshot; never fresh, so it is revalidated before counting as a hit
+    restored: bool,
+    // Strong ETag of `data`, empty for failed requests and entries stored
+    // before ETags were added
+    #[serde(default)]
+    etag: String,
+    // Pre-compressed encodings of `data`, empty when compression is disabled
+    #[serde(default)]
+    encodings: CompressedBodies,
 }
 
 impl CachedResponse {
-    fn new(data: String, status_code: u16) -> Self {
+    fn new(result: &FetchResult, ttl: Duration) -> Self {
+        let (data, status_code, error) = match result {
+            Ok((data, status_code)) => (data.clone(), *status_code, None),
+            Err(e) => (SharedBody::default(), 0, Some(e.clone())),
+        };
+        let etag = if error.is_none() {
+            etag_for(data.as_bytes())
+        } else {
+            String::new()
+        };
         Self {
             data,
-            timestamp: Instant::now(),
+            fetched_at: SystemTime::now(),
             status_code,
+            error,
+            ttl,
+            failed_at: None,
+            restored: false,
+            etag,
+            encodings: CompressedBodies::default(),
+        }
+    }
+
+    /// Rebuild an entry saved in a snapshot, keeping its original age.
+    /// `default_ttl` applies to snapshots written without a TTL.
+    fn from_snapshot(entry: SnapshotEntry, default_ttl: Duration) -> Self {
+        Self {
+            etag: etag_for(entry.data.as_bytes()),
+            data: SharedBody::from(entry.data),
+            fetched_at: snapshot::from_unix_ms(entry.fetched_at_ms),
+            status_code: entry.status_code,
+            error: None,
+            ttl: entry.ttl_ms.map_or(default_ttl, Duration::from_millis),
+            failed_at: None,
+            restored: true,
+            encodings: CompressedBodies::default(),
+        }
+    }
+
+    fn to_snapshot_entry(&self, key: &str) -> SnapshotEntry {
+        SnapshotEntry {
+            key: key.to_string(),
+            data: self.data.to_string(),
+            status_code: self.status_code,
+            fetched_at_ms: snapshot::to_unix_ms(self.fetched_at),
+            ttl_ms: Some(self.ttl.as_millis() as u64),
         }
     }
 
+    /// Time since the body was fetched from upstream.
+    pub fn age(&self) -> Duration {
+        elapsed_since(self.fetched_at)
+    }
+
     fn is_expired(&self, duration: Duration) -> bool {
-        self.timestamp.elapsed() > duration
+        self.age() > duration
+    }
+
+    fn is_fresh(&self) -> bool {
+        !self.restored && !self.is_expired(self.ttl)
+    }
+
+    fn is_success(&self) -> bool {
+        self.error.is_none() && !is_error_status(self.status_code)
+    }
+
+    /// Whether the upstream failed within the last `window`.
+    fn failed_within(&self, window: Duration) -> bool {
+        self.failed_at
+            .is_some_and(|failed_at| elapsed_since(failed_at) <= window)
+    }
+
+    /// The entry as returned to callers.
+    fn into_lookup(self, status: CacheStatus) -> CacheLookup {
+        let age = self.age();
+        let etag = if self.etag.is_empty() {
+            etag_for(self.data.as_bytes())
+        } else {
+            self.etag
+        };
+        CacheLookup {
+            age,
+            ttl: self.ttl,
+            data: self.data,
+            status_code: self.status_code,
+            etag,
+            encodings: self.encodings,
+            last_modified: self.fetched_at,
+            status,
+            stored: true,
+        }
+    }
+
+    /// The cached outcome of a failed request, as returned to callers.
+    fn into_error_result(self) -> Result<CacheLookup, String> {
+        match self.error {
+            Some(e) => Err(e),
+            None => Ok(self.into_lookup(CacheStatus::Hit)),
+        }
+    }
+
+    /// Approximate memory used by this entry when stored under `key`.
+    pub fn size_bytes(&self, key: &str) -> usize {
+        key.len()
+            + self.data.len()
+            + self.error.as_ref().map_or(0, String::len)
+            + self.encodings.size_bytes()
+    }
+}
+
+/// Wall-clock time since `time`, zero if the clock went backwards.
+fn elapsed_since(time: SystemTime) -> Duration {
+    SystemTime::now().duration_since(time).unwrap_or_default()
+}
+
+/// Strong ETag of a response body: a quoted, truncated SHA-256 of its bytes,
+/// so every replica derives the same tag for the same body.
+pub fn etag_for(body: &[u8]) -> String {
+    let digest = Sha256::digest(body);
+    let hex: String = digest[..16]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    format!("\"{}\"", hex)
+}
+
+/// Whether an upstream status code is treated as an error by the cache.
+fn is_error_status(status_code: u16) -> bool {
+    status_code >= 400
+}
+
+/// Caching rules for one proxied route.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CachePolicy {
+    /// How long successful responses are fresh.
+    pub ttl: Duration,
+    /// How long error statuses (4xx/5xx) and failed requests are cached.
+    /// Zero disables negative caching for the route.
+    pub negative_ttl: Duration,
+    /// Adapt the TTL of each key to how often its body changes, starting
+    /// from `ttl`. `None` keeps every key at `ttl`.
+    pub adaptive_ttl: Option<AdaptiveTtl>,
+}
+
+impl CachePolicy {
+    pub fn new(ttl_secs: u64, negative_ttl_secs: u64) -> Self {
+        Self {
+            ttl: Duration::from_secs(ttl_secs),
+            negative_ttl: Duration::from_secs(negative_ttl_secs),
+            adaptive_ttl: None,
+        }
+    }
+
+    /// Move the TTL of each key within `bounds` depending on how often its
+    /// body changes between refreshes.
+    pub fn with_adaptive_ttl(mut self, bounds: AdaptiveTtl) -> Self {
+        self.adaptive_ttl = Some(bounds);
+        self
+    }
+}
+
+/// A proxied endpoint: its upstream URLs in failover order, the query
+/// parameters forwarded to them and its cache policy.
+#[derive(Debug, Clone)]
+pub struct ProxyRoute {
+    /// Prefix of this route's cache keys, independent of the upstream used.
+    pub name: &'static str,
+    pub upstreams: Vec<String>,
+    pub params: &'static [QueryParam],
+    pub policy: CachePolicy,
+}
+
+impl ProxyRoute {
+    /// Cache key for an already canonical query string.
+    pub fn cache_key(&self, canonical_query: &str) -> String {
+        build_url(self.name, canonical_query)
+    }
+
+    /// Upstream URLs for an already canonical query string, in failover order.
+    pub fn upstream_urls(&self, canonical_query: &str) -> Vec<String> {
+        self.upstreams
+            .iter()
+            .map(|base_url| build_url(base_url, canonical_query))
+            .collect()
+    }
+}
+
+/// How a response returned by [`ApiCache::lookup`] was obtained.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheStatus {
+    /// Served from a fresh cache entry.
+    Hit,
+    /// Fetched from upstream for this request.
+    Miss,
+    /// Expired entry served while it is refreshed in the background.
+    Stale,
+    /// Expired entry served because the upstream request failed.
+    StaleIfError,
+}
+
+impl CacheStatus {
+    /// Value of the `X-Cache` response header.
+    pub fn as_header_value(&self) -> &'static str {
+        match self {
+            CacheStatus::Hit => "HIT",
+            CacheStatus::Miss => "MISS",
+            CacheStatus::Stale | CacheStatus::StaleIfError => "STALE",
+        }
+    }
+}
+
+/// A response body together with its cache metadata.
+#[derive(Debug, Clone)]
+pub struct CacheLookup {
+    pub data: SharedBody,
+    pub status_code: u16,
+    /// Strong ETag of `data`, see [`etag_for`].
+    pub etag: String,
+    /// Pre-compressed encodings of `data`, possibly none.
+    pub encodings: CompressedBodies,
+    /// When the body was fetched from upstream.
+    pub last_modified: SystemTime,
+    /// Time since the body was fetched from upstream.
+    pub age: Duration,
+    /// How long the body is fresh after it was fetched.
+    pub ttl: Duration,
+    pub status: CacheStatus,
+    /// Whether the body comes from a cache entry. Bodies that were not
+    /// cached, such as rate limit rejections, must not be cached downstream.
+    pub stored: bool,
+}
+
+impl CacheLookup {
+    pub fn is_stale(&self) -> bool {
+        matches!(self.status, CacheStatus::Stale | CacheStatus::StaleIfError)
+    }
+
+    /// How much longer the body is fresh, zero once it is stale.
+    pub fn remaining_ttl(&self) -> Duration {
+        if self.is_stale() {
+            return Duration::ZERO;
+        }
+        self.ttl.saturating_sub(self.age)
+    }
+}
+
+/// One cache entry as listed by the admin API.
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheEntryInfo {
+    pub key: String,
+    pub status_code: u16,
+    /// Error of a cached failed request.
+    pub error: Option<String>,
+    pub age_secs: u64,
+    pub ttl_secs: u64,
+    pub fresh: bool,
+    pub size_bytes: usize,
+    /// Requests served from this key without going upstream.
+    pub hits: u64,
+    /// Seconds since the last hit, `None` before the first hit.
+    pub last_hit_secs: Option<u64>,
+}
+
+/// Where cached responses are stored, see [`CacheStore`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheBackend {
+    Memory,
+    Redis,
+    Disk,
+}
+
+impl std::str::FromStr for CacheBackend {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "memory" => Ok(CacheBackend::Memory),
+            "redis" => Ok(CacheBackend::Redis),
+            "disk" => Ok(CacheBackend::Disk),
+            _ => Err("CACHE_BACKEND must be one of memory, redis or disk"),
+        }
     }
 }
 
@@ -120,7 +459,48 @@ pub struct Config {
     pub port: String,
     pub host: String,
     pub cache_duration_secs: u64,
+    pub cache_stale_secs: u64,
+    pub cache_stale_if_error_secs: u64,
+    pub cache_max_entries: usize,
+    pub cache_max_bytes: usize,
+    pub cache_sweep_interval_secs: u64,
+    pub cache_compression: bool,
+    pub cache_backend: CacheBackend,
+    pub cache_redis_url: String,
+    pub cache_redis_prefix: String,
+    pub cache_disk_path: String,
+    pub cache_snapshot_path: Option<String>,
+    pub cache_snapshot_interval_secs: u64,
+    pub server_list_cache_secs: u64,
+    pub player_list_cache_secs: u64,
+    pub version_cache_secs: u64,
+    pub server_list_negative_cache_secs: u64,
+    pub player_list_negative_cache_secs: u64,
+    pub version_negative_cache_secs: u64,
+    pub adaptive_ttl_min_secs: u64,
+    pub adaptive_ttl_max_secs: u64,
+    pub server_list_upstreams: Vec<String>,
+    pub server_list_page_size: usize,
+    pub server_list_max_pages: usize,
+    pub player_list_upstreams: Vec<String>,
+    pub poller_interval_secs: u64,
+    pub poller_queries: Vec<String>,
+    pub poller_learned_queries: usize,
+    pub upstream_connect_timeout_secs: u64,
+    pub upstream_read_timeout_secs: u64,
+    pub upstream_timeout_secs: u64,
+    pub upstream_user_agent: String,
+    pub upstream_max_retries: u32,
+    pub upstream_retry_backoff_ms: u64,
+    pub upstream_max_response_bytes: usize,
+    pub upstream_rate_limit: f64,
+    pub upstream_rate_limit_burst: u32,
+    pub upstream_max_concurrent: usize,
+    pub upstream_rate_limit_wait_ms: u64,
+    pub breaker_failure_threshold: u32,
+    pub breaker_cool_down_secs: u64,
     pub maps_config_path: String,
+    pub admin_token: Option<String>,
     pub android_repo_url: Option<String>,
     pub web_repo_url: Option<String>,
 }
@@ -136,9 +516,203 @@ impl Config {
             .and_then(|s| s.parse().ok())
             .unwrap_or(3);
 
+        // How long an expired entry may still be served while it is refreshed
+        // in the background, default 0 (disabled)
+        let cache_stale_secs = env::var("CACHE_STALE_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(0);
+
+        // How long after expiry the last successful response may be served
+        // when the upstream fails, default 300
+        let cache_stale_if_error_secs = env::var("CACHE_STALE_IF_ERROR_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(300);
+
+        // Cache size limits, 0 disables the corresponding limit
+        let cache_max_entries = env::var("CACHE_MAX_ENTRIES")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(1000);
+        let cache_max_bytes = env::var("CACHE_MAX_BYTES")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(64 * 1024 * 1024);
+
+        // Interval between sweeps that drop expired cache entries, default 60
+        let cache_sweep_interval_secs = env::var("CACHE_SWEEP_INTERVAL_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(60);
+
+        // Store gzip and brotli encodings of cached responses, default true
+        let cache_compression = env::var("CACHE_COMPRESSION")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(true);
+
+        // Cache storage shared between replicas, default "memory"
+        let cache_backend = match env::var("CACHE_BACKEND") {
+            Ok(backend) if !backend.is_empty() => backend.parse()?,
+            _ => CacheBackend::Memory,
+        };
+        let cache_redis_url =
+            env::var("CACHE_REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string());
+        let cache_redis_prefix =
+            env::var("CACHE_REDIS_PREFIX").unwrap_or_else(|_| "rwrs:".to_string());
+        let cache_disk_path = env::var("CACHE_DISK_PATH").unwrap_or_else(|_| "cache".to_string());
+
+        // File the cache is saved to and restored from, disabled when unset
+        let cache_snapshot_path = env::var("CACHE_SNAPSHOT_PATH")
+            .ok()
+            .filter(|path| !path.is_empty());
+        // Interval between snapshots, default 300; 0 only saves on shutdown
+        let cache_snapshot_interval_secs = env::var("CACHE_SNAPSHOT_INTERVAL_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(300);
+
+        // How long responses are fresh per route, the proxied routes default
+        // to CACHE_DURATION_SECS and GitHub version lookups to 300
+        let server_list_cache_secs = env::var("SERVER_LIST_CACHE_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(cache_duration_secs);
+        let player_list_cache_secs = env::var("PLAYER_LIST_CACHE_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(cache_duration_secs);
+        let version_cache_secs = env::var("VERSION_CACHE_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(300);
+
+        // How long upstream errors are cached per route, default 1 (60 for GitHub)
+        let server_list_negative_cache_secs = env::var("SERVER_LIST_NEGATIVE_CACHE_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(1);
+        let player_list_negative_cache_secs = env::var("PLAYER_LIST_NEGATIVE_CACHE_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(1);
+        let version_negative_cache_secs = env::var("VERSION_NEGATIVE_CACHE_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(60);
+
+        // Bounds of the adaptive TTL of the proxied routes, a maximum of 0
+        // (the default) keeps their fixed TTLs
+        let adaptive_ttl_min_secs = env::var("ADAPTIVE_TTL_MIN_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(1);
+        let adaptive_ttl_max_secs = env::var("ADAPTIVE_TTL_MAX_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(0);
+
+        // Upstream base URLs, comma separated and tried in order
+        let server_list_upstreams = upstream_list(
+            "SERVER_LIST_UPSTREAMS",
+            "http://rwr.runningwithrifles.com/rwr_server_list/get_server_list.php",
+        );
+        let player_list_upstreams = upstream_list(
+            "PLAYER_LIST_UPSTREAMS",
+            "http://rwr.runningwithrifles.com/rwr_stats/view_players.php",
+        );
+
+        // Pages of the server list merged by /api/servers
+        let server_list_page_size = env::var("SERVER_LIST_PAGE_SIZE")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(100);
+        let server_list_max_pages = env::var("SERVER_LIST_MAX_PAGES")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(20);
+
+        // Background refresh of hot server list queries, 0 disables the poller
+        let poller_interval_secs = env::var("POLLER_INTERVAL_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(0);
+        let poller_queries = env::var("POLLER_QUERIES")
+            .unwrap_or_default()
+            .split(',')
+            .map(|query| query.trim().to_string())
+            .filter(|query| !query.is_empty())
+            .collect();
+        let poller_learned_queries = env::var("POLLER_LEARNED_QUERIES")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(5);
+
+        // Shared upstream HTTP client settings
+        let upstream_connect_timeout_secs = env::var("UPSTREAM_CONNECT_TIMEOUT_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(5);
+        let upstream_read_timeout_secs = env::var("UPSTREAM_READ_TIMEOUT_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(10);
+        let upstream_timeout_secs = env::var("UPSTREAM_TIMEOUT_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(10);
+        let upstream_user_agent = env::var("UPSTREAM_USER_AGENT")
+            .unwrap_or_else(|_| format!("rwrs-server/{}", env!("CARGO_PKG_VERSION")));
+        let upstream_max_retries = env::var("UPSTREAM_MAX_RETRIES")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(2);
+        let upstream_retry_backoff_ms = env::var("UPSTREAM_RETRY_BACKOFF_MS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(200);
+        let upstream_max_response_bytes = env::var("UPSTREAM_MAX_RESPONSE_BYTES")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(8 * 1024 * 1024);
+
+        // Global budget of upstream requests, a rate or cap of 0 disables it
+        let upstream_rate_limit = env::var("UPSTREAM_RATE_LIMIT")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(10.0);
+        let upstream_rate_limit_burst = env::var("UPSTREAM_RATE_LIMIT_BURST")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(20);
+        let upstream_max_concurrent = env::var("UPSTREAM_MAX_CONCURRENT")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(8);
+        let upstream_rate_limit_wait_ms = env::var("UPSTREAM_RATE_LIMIT_WAIT_MS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(1000);
+
+        // Circuit breaker for upstream hosts, a threshold of 0 disables it
+        let breaker_failure_threshold = env::var("BREAKER_FAILURE_THRESHOLD")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(5);
+        let breaker_cool_down_secs = env::var("BREAKER_COOL_DOWN_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(30);
+
         // Maps config file path, default "maps.json"
         let maps_config_path = env::var("MAPS_CONFIG").unwrap_or_else(|_| "maps.json".to_string());
 
+        // Bearer token of the admin API, which is disabled while it is unset
+        let admin_token = env::var("ADMIN_TOKEN")
+            .ok()
+            .filter(|token| !token.is_empty());
+
         // Repository URLs for version info
         let android_repo_url = env::var("ANDROID_REPO_URL").ok();
         let web_repo_url = env::var("WEB_REPO_URL").ok();
@@ -147,86 +721,969 @@ impl Config {
             port: port.to_string(),
             host: host.to_string(),
             cache_duration_secs,
+            cache_stale_secs,
+            cache_stale_if_error_secs,
+            cache_max_entries,
+            cache_max_bytes,
+            cache_sweep_interval_secs,
+            cache_compression,
+            cache_backend,
+            cache_redis_url,
+            cache_redis_prefix,
+            cache_disk_path,
+            cache_snapshot_path,
+            cache_snapshot_interval_secs,
+            server_list_cache_secs,
+            player_list_cache_secs,
+            version_cache_secs,
+            server_list_negative_cache_secs,
+            player_list_negative_cache_secs,
+            version_negative_cache_secs,
+            adaptive_ttl_min_secs,
+            adaptive_ttl_max_secs,
+            server_list_upstreams,
+            server_list_page_size,
+            server_list_max_pages,
+            player_list_upstreams,
+            poller_interval_secs,
+            poller_queries,
+            poller_learned_queries,
+            upstream_connect_timeout_secs,
+            upstream_read_timeout_secs,
+            upstream_timeout_secs,
+            upstream_user_agent,
+            upstream_max_retries,
+            upstream_retry_backoff_ms,
+            upstream_max_response_bytes,
+            upstream_rate_limit,
+            upstream_rate_limit_burst,
+            upstream_max_concurrent,
+            upstream_rate_limit_wait_ms,
+            breaker_failure_threshold,
+            breaker_cool_down_secs,
             maps_config_path,
+            admin_token,
             android_repo_url,
             web_repo_url,
         })
     }
+
+    /// Settings for the shared upstream HTTP client.
+    pub fn upstream_config(&self) -> UpstreamConfig {
+        UpstreamConfig {
+            connect_timeout: Duration::from_secs(self.upstream_connect_timeout_secs),
+            read_timeout: Duration::from_secs(self.upstream_read_timeout_secs),
+            request_timeout: Duration::from_secs(self.upstream_timeout_secs),
+            user_agent: self.upstream_user_agent.clone(),
+            max_retries: self.upstream_max_retries,
+            retry_backoff: Duration::from_millis(self.upstream_retry_backoff_ms),
+            max_response_bytes: self.upstream_max_response_bytes,
+        }
+    }
+
+    /// The configured cache store. Memory stores are bounded by the cache
+    /// limits; Redis and disk stores rely on entry expiry instead.
+    pub fn cache_store(&self) -> Result<Arc<dyn CacheStore>, String> {
+        Ok(match self.cache_backend {
+            CacheBackend::Memory => Arc::new(MemoryStore::new(
+                self.cache_max_entries,
+                self.cache_max_bytes,
+            )),
+            CacheBackend::Redis => Arc::new(RedisStore::new(
+                &self.cache_redis_url,
+                &self.cache_redis_prefix,
+            )?),
+            CacheBackend::Disk => Arc::new(DiskStore::new(&self.cache_disk_path)),
+        })
+    }
+
+    /// Settings of the server list poller, `None` when it is disabled.
+    pub fn poller_config(&self) -> Option<PollerConfig> {
+        if self.poller_interval_secs == 0 {
+            return None;
+        }
+        Some(PollerConfig {
+            interval: Duration::from_secs(self.poller_interval_secs),
+            queries: self.poller_queries.clone(),
+            learned_queries: self.poller_learned_queries,
+        })
+    }
+
+    pub fn circuit_breaker_config(&self) -> CircuitBreakerConfig {
+        CircuitBreakerConfig::new(self.breaker_failure_threshold, self.breaker_cool_down_secs)
+    }
+
+    pub fn rate_limit_config(&self) -> RateLimitConfig {
+        RateLimitConfig::new(
+            self.upstream_rate_limit,
+            self.upstream_rate_limit_burst,
+            self.upstream_max_concurrent,
+            self.upstream_rate_limit_wait_ms,
+        )
+    }
+
+    /// Upstreams and cache policy for `/api/server_list`.
+    pub fn server_list_route(&self) -> ProxyRoute {
+        ProxyRoute {
+            name: "server_list",
+            upstreams: self.server_list_upstreams.clone(),
+            params: SERVER_LIST_PARAMS,
+            policy: self.server_list_policy(),
+        }
+    }
+
+    /// How `/api/servers` merges the pages of the server list.
+    pub fn server_snapshot_config(&self) -> ServerSnapshotConfig {
+        ServerSnapshotConfig {
+            page_size: self.server_list_page_size,
+            max_pages: self.server_list_max_pages,
+        }
+    }
+
+    /// Upstreams and cache policy for `/api/player_list`.
+    pub fn player_list_route(&self) -> ProxyRoute {
+        ProxyRoute {
+            name: "player_list",
+            upstreams: self.player_list_upstreams.clone(),
+            params: PLAYER_LIST_PARAMS,
+            policy: self.player_list_policy(),
+        }
+    }
+
+    /// Bounds of the adaptive TTL of the proxied routes, `None` when it is
+    /// disabled.
+    pub fn adaptive_ttl(&self) -> Option<AdaptiveTtl> {
+        if self.adaptive_ttl_max_secs == 0 {
+            return None;
+        }
+        Some(AdaptiveTtl::new(
+            self.adaptive_ttl_min_secs,
+            self.adaptive_ttl_max_secs,
+        ))
+    }
+
+    /// Cache policy for `/api/server_list`.
+    pub fn server_list_policy(&self) -> CachePolicy {
+        self.proxy_policy(
+            self.server_list_cache_secs,
+            self.server_list_negative_cache_secs,
+        )
+    }
+
+    /// Cache policy for `/api/player_list`.
+    pub fn player_list_policy(&self) -> CachePolicy {
+        self.proxy_policy(
+            self.player_list_cache_secs,
+            self.player_list_negative_cache_secs,
+        )
+    }
+
+    fn proxy_policy(&self, ttl_secs: u64, negative_ttl_secs: u64) -> CachePolicy {
+        let policy = CachePolicy::new(ttl_secs, negative_ttl_secs);
+        match self.adaptive_ttl() {
+            Some(bounds) => policy.with_adaptive_ttl(bounds),
+            None => policy,
+        }
+    }
+
+    /// Cache policy for GitHub release lookups of `/api/version`.
+    pub fn version_policy(&self) -> CachePolicy {
+        CachePolicy::new(self.version_cache_secs, self.version_negative_cache_secs)
+    }
+}
+
+/// Read a comma separated list of URLs from `key`, falling back to `default`.
+fn upstream_list(key: &str, default: &str) -> Vec<String> {
+    let urls: Vec<String> = env::var(key)
+        .unwrap_or_default()
+        .split(',')
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty())
+        .collect();
+    if urls.is_empty() {
+        vec![default.to_string()]
+    } else {
+        urls
+    }
 }
 
+type FetchResult = Result<(SharedBody, u16), String>;
+
+#[derive(Clone)]
 pub struct ApiCache {
-    cache: Arc<RwLock<HashMap<String, CachedResponse>>>,
-    cache_expiry_duration: Duration,
+    store: Arc<dyn CacheStore>,
+    client: UpstreamClient,
+    // One circuit breaker per upstream origin
+    breakers: Arc<Mutex<HashMap<String, CircuitBreaker>>>,
+    breaker_config: CircuitBreakerConfig,
+    // Budget shared by every upstream fetch
+    limiter: RateLimiter,
+    // Upstream fetches currently running, keyed by URL, so concurrent misses share one request
+    in_flight: Arc<Mutex<HashMap<String, broadcast::Sender<FetchResult>>>>,
+    // Body change history of keys cached under an adaptive TTL policy
+    change_trackers: Arc<Mutex<HashMap<String, ChangeTracker>>>,
+    // Hits per key, kept here so they are counted with every store
+    access_stats: Arc<RwLock<HashMap<String, AccessStats>>>,
+    stale_window: Duration,
+    stale_if_error: Duration,
+    default_policy: CachePolicy,
+    compression: bool,
 }
 
 impl ApiCache {
+    /// Cache whose default policy keeps responses fresh for `cache_expiry_secs`.
+    /// Routes pass their own [`CachePolicy`] to override it.
     pub fn new(cache_expiry_secs: u64) -> Self {
         Self {
-            cache: Arc::new(RwLock::new(HashMap::new())),
-            cache_expiry_duration: Duration::from_secs(cache_expiry_secs),
+            store: Arc::new(MemoryStore::default()),
+            client: UpstreamClient::new(UpstreamConfig::default())
+                .expect("Failed to build default HTTP client"),
+            breakers: Arc::new(Mutex::new(HashMap::new())),
+            breaker_config: CircuitBreakerConfig::disabled(),
+            limiter: RateLimiter::new(RateLimitConfig::disabled()),
+            in_flight: Arc::new(Mutex::new(HashMap::new())),
+            change_trackers: Arc::new(Mutex::new(HashMap::new())),
+            access_stats: Arc::new(RwLock::new(HashMap::new())),
+            stale_window: Duration::ZERO,
+            stale_if_error: Duration::ZERO,
+            default_policy: CachePolicy::new(cache_expiry_secs, 0),
+            compression: false,
         }
     }
 
-    pub async fn get_cached_response(&self, url: &str) -> Result<(String, u16), String> {
-        // Check cache for this specific URL
-        {
-            let cache_guard = self.cache.read().await;
-            if let Some(cached) = cache_guard.get(url) {
-                if !cached.is_expired(self.cache_expiry_duration) {
+    /// Send upstream requests through `client` instead of a default client.
+    pub fn with_client(mut self, client: UpstreamClient) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Guard every upstream origin with a circuit breaker so requests fail
+    /// fast while that origin is unhealthy.
+    pub fn with_circuit_breaker(mut self, config: CircuitBreakerConfig) -> Self {
+        self.breaker_config = config;
+        self
+    }
+
+    /// Limit the rate and concurrency of upstream requests across all keys,
+    /// retries and failover included. A fetch that would exceed the budget is
+    /// not sent; callers get stale data when stale-if-error allows it and a
+    /// 503 response otherwise.
+    pub fn with_rate_limit(mut self, config: RateLimitConfig) -> Self {
+        self.limiter = RateLimiter::new(config);
+        self
+    }
+
+    /// Allow expired entries to be served for up to `stale_secs` more seconds
+    /// while a background task refreshes them (stale-while-revalidate).
+    pub fn with_stale_window(mut self, stale_secs: u64) -> Self {
+        self.stale_window = Duration::from_secs(stale_secs);
+        self
+    }
+
+    /// Serve the last successful response for up to `max_stale_secs` after
+    /// expiry when the upstream request fails or answers with an error status
+    /// (stale-if-error).
+    pub fn with_stale_if_error(mut self, max_stale_secs: u64) -> Self {
+        self.stale_if_error = Duration::from_secs(max_stale_secs);
+        self
+    }
+
+    /// Policy used by [`ApiCache::lookup`] and [`ApiCache::get_cached_response`].
+    /// By default responses are fresh for the TTL given to [`ApiCache::new`]
+    /// and errors are not cached.
+    pub fn with_default_policy(mut self, policy: CachePolicy) -> Self {
+        self.default_policy = policy;
+        self
+    }
+
+    /// Store gzip and brotli encodings of every successful response next to
+    /// its body, so handlers can serve them without compressing per request.
+    pub fn with_compression(mut self, enabled: bool) -> Self {
+        self.compression = enabled;
+        self
+    }
+
+    /// Keep entries in `store` instead of the default in-memory store.
+    pub fn with_store(mut self, store: Arc<dyn CacheStore>) -> Self {
+        self.store = store;
+        self
+    }
+
+    /// Use an in-memory store bounded to `max_entries` entries and
+    /// `max_bytes` bytes of keys and bodies, evicting the least recently used
+    /// entries first. A limit of 0 disables that limit.
+    pub fn with_limits(self, max_entries: usize, max_bytes: usize) -> Self {
+        self.with_store(Arc::new(MemoryStore::new(max_entries, max_bytes)))
+    }
+
+    /// Entry count and size of the store, zero when it cannot be reached.
+    pub async fn stats(&self) -> StoreStats {
+        self.store.stats().await.unwrap_or_else(|e| {
+            error!("Failed to read cache stats: {}", e);
+            StoreStats::default()
+        })
+    }
+
+    /// Number of entries currently cached.
+    pub async fn len(&self) -> usize {
+        self.stats().await.entries
+    }
+
+    pub async fn is_empty(&self) -> bool {
+        self.len().await == 0
+    }
+
+    /// Total size of cached keys and bodies in bytes.
+    pub async fn size_bytes(&self) -> usize {
+        self.stats().await.size_bytes
+    }
+
+    /// Circuit breaker state of every upstream origin contacted so far.
+    pub fn upstream_status(&self) -> Vec<BreakerStatus> {
+        let breakers = self.breakers.lock().unwrap();
+        let mut status: Vec<BreakerStatus> =
+            breakers.values().map(CircuitBreaker::status).collect();
+        status.sort_by(|a, b| a.upstream.cmp(&b.upstream));
+        status
+    }
+
+    /// Remaining budget of the upstream rate limiter.
+    pub fn rate_limit_status(&self) -> RateLimitStatus {
+        self.limiter.status()
+    }
+
+    /// Effective TTL of `key`, `None` unless it is cached under an adaptive
+    /// TTL policy.
+    pub fn effective_ttl(&self, key: &str) -> Option<Duration> {
+        let trackers = self.change_trackers.lock().unwrap();
+        trackers.get(key).map(ChangeTracker::ttl)
+    }
+
+    /// Effective TTL and change rate of every key cached under an adaptive
+    /// TTL policy.
+    pub fn adaptive_ttl_status(&self) -> Vec<AdaptiveTtlStatus> {
+        let trackers = self.change_trackers.lock().unwrap();
+        let mut status: Vec<AdaptiveTtlStatus> = trackers
+            .iter()
+            .map(|(key, tracker)| tracker.status(key))
+            .collect();
+        status.sort_by(|a, b| a.key.cmp(&b.key));
+        status
+    }
+
+    /// Drop every entry that is too old to be served, even as stale data.
+    /// Returns the number of entries removed.
+    pub async fn sweep_expired(&self) -> usize {
+        self.prune_change_trackers(&mut self.change_trackers.lock().unwrap());
+        self.access_stats
+            .write()
+            .unwrap()
+            .retain(|_, stats| stats.is_retained());
+
+        self.store.sweep_expired().await.unwrap_or_else(|e| {
+            error!("Failed to sweep the cache: {}", e);
+            0
+        })
+    }
+
+    /// Drop the change history of keys not refreshed within their retention.
+    /// Their entries are gone from the cache, so the history is stale as well.
+    fn prune_change_trackers(&self, trackers: &mut HashMap<String, ChangeTracker>) {
+        let max_stale = self.stale_window.max(self.stale_if_error);
+        trackers.retain(|_, tracker| tracker.idle_for() <= tracker.ttl() + max_stale);
+    }
+
+    /// Periodically run [`ApiCache::sweep_expired`] in a background task.
+    pub fn spawn_sweeper(&self, interval: Duration) -> JoinHandle<()> {
+        let this = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            // The first tick completes immediately
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let removed = this.sweep_expired().await;
+                if removed > 0 {
+                    info!("Cache sweeper removed {} expired entries", removed);
+                }
+            }
+        })
+    }
+
+    /// How long an entry may be kept before it can no longer be served at all.
+    fn retention(&self, entry: &CachedResponse) -> Duration {
+        if entry.is_success() {
+            entry.ttl + self.stale_window.max(self.stale_if_error)
+        } else {
+            entry.ttl
+        }
+    }
+
+    /// The entry stored under `key`. An unreachable store counts as a miss
+    /// so requests still reach the upstream.
+    async fn cached(&self, key: &str) -> Option<CachedResponse> {
+        self.store.get(key).await.unwrap_or_else(|e| {
+            error!("Failed to read {} from the cache: {}", key, e);
+            None
+        })
+    }
+
+    async fn store_entry(&self, key: &str, entry: CachedResponse) {
+        let retention = self.retention(&entry);
+        // Hits survive refreshes of the key, so its counters live as long as its entry
+        if let Some(stats) = self.access_stats.read().unwrap().get(key) {
+            stats.retain_until(entry.fetched_at + retention);
+        }
+        if let Err(e) = self.store.put(key, entry, retention).await {
+            error!("Failed to store {} in the cache: {}", key, e);
+        }
+    }
+
+    /// Count a request answered from `cached`, the entry of `key`.
+    fn record_hit(&self, key: &str, cached: &CachedResponse) {
+        let retained_until = cached.fetched_at + self.retention(cached);
+        if let Some(stats) = self.access_stats.read().unwrap().get(key) {
+            stats.record_hit();
+            stats.retain_until(retained_until);
+            return;
+        }
+        let mut access_stats = self.access_stats.write().unwrap();
+        // Pruned here as well, so the map stays bounded without a sweeper
+        access_stats.retain(|_, stats| stats.is_retained());
+        let stats = access_stats.entry(key.to_string()).or_default();
+        stats.record_hit();
+        stats.retain_until(retained_until);
+    }
+
+    /// `cached`, the entry of `key`, as returned to callers of a hit.
+    fn serve_cached(&self, key: &str, cached: CachedResponse, status: CacheStatus) -> CacheLookup {
+        self.record_hit(key, &cached);
+        cached.into_lookup(status)
+    }
+
+    /// Forget the change history and hits of `key`, whose entry is gone.
+    fn forget_key(&self, key: &str) {
+        self.change_trackers.lock().unwrap().remove(key);
+        self.access_stats.write().unwrap().remove(key);
+    }
+
+    pub async fn get_cached_response(&self, url: &str) -> FetchResult {
+        self.lookup(url)
+            .await
+            .map(|lookup| (lookup.data, lookup.status_code))
+    }
+
+    /// Return the response for `url` together with its cache metadata,
+    /// fetching it from upstream when no servable entry is cached.
+    pub async fn lookup(&self, url: &str) -> Result<CacheLookup, String> {
+        self.lookup_with_policy(url, self.default_policy).await
+    }
+
+    /// Like [`ApiCache::lookup`], caching errors according to `policy`.
+    pub async fn lookup_with_policy(
+        &self,
+        url: &str,
+        policy: CachePolicy,
+    ) -> Result<CacheLookup, String> {
+        self.lookup_with_failover(url, &[url.to_string()], policy)
+            .await
+    }
+
+    /// Look up the request `query` of a proxied route, trying the route's
+    /// upstreams in order when the response is not cached.
+    pub async fn lookup_route(
+        &self,
+        route: &ProxyRoute,
+        query: &str,
+    ) -> Result<CacheLookup, String> {
+        let query = canonical_query(query, route.params);
+        self.lookup_with_failover(
+            &route.cache_key(&query),
+            &route.upstream_urls(&query),
+            route.policy,
+        )
+        .await
+    }
+
+    /// Fetch `query` of a proxied route from upstream and store it, even if a
+    /// fresh entry is cached. Joins a fetch of the same key already running.
+    pub async fn refresh_route(&self, route: &ProxyRoute, query: &str) -> FetchResult {
+        let query = canonical_query(query, route.params);
+        self.fetch_coalesced(
+            &route.cache_key(&query),
+            &route.upstream_urls(&query),
+            route.policy,
+        )
+        .await
+    }
+
+    /// Return the response cached under `key`, fetching it from the first
+    /// healthy URL of `urls` when no servable entry is cached. A URL is
+    /// skipped when its circuit is open and abandoned for the next one on a
+    /// failed request or 5xx status.
+    pub async fn lookup_with_failover(
+        &self,
+        key: &str,
+        urls: &[String],
+        policy: CachePolicy,
+    ) -> Result<CacheLookup, String> {
+        // Check cache for this specific key
+        if let Some(cached) = self.cached(key).await {
+            if !cached.is_success() {
+                if cached.is_fresh() {
                     info!(
-                        "Cache hit for {}, age: {:?}",
-                        url,
-                        cached.timestamp.elapsed()
+                        "Cached upstream error for {}, status: {}",
+                        key, cached.status_code
                     );
-                    return Ok((cached.data.clone(), cached.status_code));
-                } else {
-                    info!("Cache expired for {}, refreshing required", url);
+                    self.record_hit(key, &cached);
+                    return cached.into_error_result();
                 }
+                info!("Cached upstream error for {} expired, retrying", key);
+            } else if cached.is_fresh() {
+                info!("Cache hit for {}, age: {:?}", key, cached.age());
+                return Ok(self.serve_cached(key, cached, CacheStatus::Hit));
+            } else if cached.failed_within(policy.negative_ttl)
+                && !cached.is_expired(cached.ttl + self.stale_if_error)
+            {
+                info!(
+                    "Upstream recently failed for {}, serving stale response, age: {:?}",
+                    key,
+                    cached.age()
+                );
+                return Ok(self.serve_cached(key, cached, CacheStatus::StaleIfError));
+            } else if !cached.is_expired(cached.ttl + self.stale_window) {
+                info!(
+                    "Serving stale cache for {}, age: {:?}, refreshing in background",
+                    key,
+                    cached.age()
+                );
+                // Nobody waits for the result; the refresh only updates the cache
+                drop(self.start_fetch(key, urls, policy));
+                return Ok(self.serve_cached(key, cached, CacheStatus::Stale));
             } else {
-                info!("No cache data available for {}, fetching from API", url);
+                info!("Cache expired for {}, refreshing required", key);
             }
+        } else {
+            info!("No cache data available for {}, fetching from API", key);
+            // The entry was evicted or removed, and its history and hits with it
+            self.forget_key(key);
         }
 
-        // Make actual API call
-        info!("Making request to external API: {}", url);
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(10))
-            .build()
-            .unwrap();
-
-        match client.get(url).send().await {
-            Ok(response) => {
-                let status = response.status().as_u16();
-                info!("Received response with status: {}", status);
-                match response.text().await {
-                    Ok(text) => {
-                        info!("Successfully fetched {} bytes from API", text.len());
-                        // Cache the response with URL as key
-                        self.update_cache(url.to_string(), text.clone(), status)
-                            .await;
-                        Ok((text, status))
-                    }
-                    Err(e) => {
-                        error!("Failed to read response body: {}", e);
-                        Err(format!("Failed to read response body: {}", e))
+        let result = self.fetch_coalesced(key, urls, policy).await;
+        let failed = match &result {
+            Ok((_, status_code)) => is_error_status(*status_code),
+            Err(_) => true,
+        };
+        if failed && let Some(stale) = self.stale_if_error_lookup(key).await {
+            warn!(
+                "Upstream request for {} failed, serving stale response, age: {:?}",
+                key, stale.age
+            );
+            return Ok(stale);
+        }
+
+        // Serve the entry stored by the fetch, which has the body's encodings
+        if let Ok((data, _)) = &result
+            && let Some(entry) = self.cached(key).await
+            && entry.data == *data
+        {
+            return Ok(entry.into_lookup(CacheStatus::Miss));
+        }
+
+        let ttl = if failed {
+            policy.negative_ttl
+        } else {
+            self.effective_ttl(key).unwrap_or(policy.ttl)
+        };
+        result.map(|(data, status_code)| CacheLookup {
+            etag: etag_for(data.as_bytes()),
+            encodings: CompressedBodies::default(),
+            data,
+            status_code,
+            last_modified: SystemTime::now(),
+            age: Duration::ZERO,
+            ttl,
+            status: CacheStatus::Miss,
+            stored: false,
+        })
+    }
+
+    /// The last successful response for `key`, if it is recent enough to be
+    /// served in place of an upstream error.
+    async fn stale_if_error_lookup(&self, key: &str) -> Option<CacheLookup> {
+        let cached = self.cached(key).await?;
+        if cached.is_success() && !cached.is_expired(cached.ttl + self.stale_if_error) {
+            Some(self.serve_cached(key, cached, CacheStatus::StaleIfError))
+        } else {
+            None
+        }
+    }
+
+    /// Wait for the shared upstream fetch of `key`.
+    async fn fetch_coalesced(
+        &self,
+        key: &str,
+        urls: &[String],
+        policy: CachePolicy,
+    ) -> FetchResult {
+        let mut receiver = self.start_fetch(key, urls, policy);
+        match receiver.recv().await {
+            Ok(result) => result,
+            Err(e) => Err(format!(
+                "In-flight request for {} was abandoned: {}",
+                key, e
+            )),
+        }
+    }
+
+    /// Subscribe to the in-flight fetch of `key`, starting one if none is running.
+    ///
+    /// The fetch runs in its own task so that a cancelled caller never leaves
+    /// the other waiters without a result.
+    fn start_fetch(
+        &self,
+        key: &str,
+        urls: &[String],
+        policy: CachePolicy,
+    ) -> broadcast::Receiver<FetchResult> {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if let Some(sender) = in_flight.get(key) {
+            info!("Joining in-flight request for {}", key);
+            return sender.subscribe();
+        }
+
+        let (sender, receiver) = broadcast::channel(1);
+        in_flight.insert(key.to_string(), sender.clone());
+        drop(in_flight);
+
+        let this = self.clone();
+        let key = key.to_string();
+        let urls = urls.to_vec();
+        tokio::spawn(async move {
+            let result = match this.fetch_upstream(&urls).await {
+                Ok(result) => {
+                    this.update_cache(&key, &result, policy).await;
+                    result
+                }
+                // Nothing was sent, so the cache learns nothing about the upstream
+                Err(e) => {
+                    warn!("Not fetching {}: {}", key, e);
+                    Ok((SharedBody::from(e), 503))
+                }
+            };
+            // Unregister before publishing so every subscriber is guaranteed to
+            // receive this result and later callers go through the cache again
+            this.in_flight.lock().unwrap().remove(&key);
+            let _ = sender.send(result);
+        });
+
+        receiver
+    }
+
+    /// Fetch the first of `urls` that answers without a 5xx status. Every
+    /// request sent, retries and failover included, takes a permit from the
+    /// rate limiter. Fails only when the limiter rejected the fetch before
+    /// any request was sent.
+    async fn fetch_upstream(&self, urls: &[String]) -> Result<FetchResult, String> {
+        let mut last_result = Err("No upstream URL configured".to_string());
+        let mut sent = false;
+        for (index, url) in urls.iter().enumerate() {
+            let breaker = self.breaker_for(url);
+            if let Err(e) = breaker.try_acquire() {
+                warn!("Skipping request to {}: {}", url, e);
+                last_result = Err(e);
+                continue;
+            }
+            let permit = match self.limiter.acquire().await {
+                Ok(permit) => permit,
+                Err(e) => {
+                    // Not sent, so the breaker learns nothing about this upstream
+                    breaker.release();
+                    if !sent {
+                        return Err(e);
                     }
+                    warn!("Not trying {}: {}", url, e);
+                    break;
+                }
+            };
+            sent = true;
+
+            // Retries are spent on the last upstream only, failing over is quicker
+            let retry = index + 1 == urls.len();
+            let result = self
+                .client
+                .get_text_limited(url, &self.limiter, permit, retry)
+                .await
+                .map(|(body, status_code)| (SharedBody::from(body), status_code));
+            match &result {
+                Ok((_, status_code)) if *status_code >= 500 => {
+                    breaker.record_failure(&format!("Upstream returned status {}", status_code))
+                }
+                Ok(_) => {
+                    breaker.record_success();
+                    return Ok(result);
                 }
+                Err(e) => breaker.record_failure(e),
             }
-            Err(e) => {
-                error!("Request failed: {}", e);
-                Err(format!("Request failed: {}", e))
+            if urls.len() > 1 {
+                warn!("Upstream {} failed, trying next upstream", url);
             }
+            last_result = result;
         }
+        Ok(last_result)
     }
 
-    async fn update_cache(&self, url: String, data: String, status_code: u16) {
-        let cached_response = CachedResponse::new(data, status_code);
-        let mut cache_guard = self.cache.write().await;
-        cache_guard.insert(url, cached_response);
+    /// The circuit breaker for the origin (scheme, host and port) of `url`.
+    fn breaker_for(&self, url: &str) -> CircuitBreaker {
+        let origin = reqwest::Url::parse(url)
+            .map(|parsed| parsed.origin().ascii_serialization())
+            .unwrap_or_else(|_| url.to_string());
+        self.breakers
+            .lock()
+            .unwrap()
+            .entry(origin.clone())
+            .or_insert_with(|| CircuitBreaker::new(&origin, self.breaker_config))
+            .clone()
+    }
+
+    /// TTL of a successful response for `key`, adapted to how often its body
+    /// changed when `policy` has an adaptive TTL.
+    fn success_ttl(
+        &self,
+        key: &str,
+        data: &str,
+        status_code: u16,
+        policy: CachePolicy,
+    ) -> Duration {
+        let Some(bounds) = policy.adaptive_ttl else {
+            return policy.ttl;
+        };
+        let mut trackers = self.change_trackers.lock().unwrap();
+        match trackers.get_mut(key) {
+            Some(tracker) => {
+                let ttl = tracker.observe(data, status_code, bounds);
+                info!("Adaptive TTL of {} is now {:?}", key, ttl);
+                ttl
+            }
+            None => {
+                // Pruned here as well, so the map stays bounded without a sweeper
+                self.prune_change_trackers(&mut trackers);
+                let tracker = ChangeTracker::new(data, status_code, policy.ttl, bounds);
+                let ttl = tracker.ttl();
+                trackers.insert(key.to_string(), tracker);
+                ttl
+            }
+        }
+    }
+
+    /// Store the outcome of an upstream request for `key` according to `policy`.
+    async fn update_cache(&self, key: &str, result: &FetchResult, policy: CachePolicy) {
+        let failed = match result {
+            Ok((_, status_code)) => is_error_status(*status_code),
+            Err(_) => true,
+        };
+        let ttl = match result {
+            Ok((data, status_code)) if !failed => self.success_ttl(key, data, *status_code, policy),
+            _ => policy.negative_ttl,
+        };
+
+        let existing = self.cached(key).await;
+        if failed {
+            // Never replace a good response that can still be served with an
+            // error; remember the failure so callers are not sent upstream again
+            if let Some(existing) = &existing
+                && existing.is_success()
+                && !existing.is_expired(self.retention(existing))
+            {
+                info!(
+                    "Keeping last good response for {} after upstream error",
+                    key
+                );
+                let mut kept = existing.clone();
+                kept.failed_at = Some(SystemTime::now());
+                self.store_entry(key, kept).await;
+                return;
+            }
+            if ttl.is_zero() {
+                return;
+            }
+        }
+
+        let mut entry = CachedResponse::new(result, ttl);
+        if !failed {
+            entry.encodings = self.compress(&entry.data).await;
+        }
+        self.store_entry(key, entry).await;
+    }
+
+    /// Pre-compressed encodings of `data`, none when compression is disabled.
+    async fn compress(&self, data: &SharedBody) -> CompressedBodies {
+        if !self.compression {
+            return CompressedBodies::default();
+        }
+        let data = data.clone();
+        tokio::task::spawn_blocking(move || CompressedBodies::compress(&data))
+            .await
+            .unwrap_or_default()
+    }
+
+    /// Every cached entry whose key starts with `prefix`, sorted by key.
+    pub async fn entries(&self, prefix: &str) -> Result<Vec<CacheEntryInfo>, String> {
+        let stored = self.store.entries().await?;
+        let access_stats = self.access_stats.read().unwrap();
+        let mut entries: Vec<CacheEntryInfo> = stored
+            .into_iter()
+            .filter(|(key, _)| key.starts_with(prefix))
+            .map(|(key, entry)| CacheEntryInfo {
+                size_bytes: entry.size_bytes(&key),
+                status_code: entry.status_code,
+                age_secs: entry.age().as_secs(),
+                ttl_secs: entry.ttl.as_secs(),
+                fresh: entry.is_fresh(),
+                hits: access_stats.get(&key).map_or(0, AccessStats::hits),
+                last_hit_secs: access_stats
+                    .get(&key)
+                    .and_then(AccessStats::since_last_hit)
+                    .map(|age| age.as_secs()),
+                error: entry.error,
+                key,
+            })
+            .collect();
+        entries.sort_by(|a, b| a.key.cmp(&b.key));
+        Ok(entries)
+    }
+
+    /// Remove `key` from the cache, so the next request refetches it.
+    /// Returns whether it was cached.
+    pub async fn purge(&self, key: &str) -> Result<bool, String> {
+        self.forget_key(key);
+        self.store.remove(key).await
+    }
+
+    /// Remove every key starting with `prefix`, everything for an empty
+    /// prefix. Returns the number of entries removed.
+    pub async fn purge_prefix(&self, prefix: &str) -> Result<usize, String> {
+        self.change_trackers
+            .lock()
+            .unwrap()
+            .retain(|key, _| !key.starts_with(prefix));
+        self.access_stats
+            .write()
+            .unwrap()
+            .retain(|key, _| !key.starts_with(prefix));
+        let mut purged = 0;
+        for (key, _) in self.store.entries().await? {
+            if key.starts_with(prefix) && self.store.remove(&key).await? {
+                purged += 1;
+            }
+        }
+        Ok(purged)
+    }
+
+    /// Successful responses currently cached, least recently used first.
+    /// Errors are left out since they are only cached briefly.
+    pub async fn snapshot(&self) -> Result<CacheSnapshot, String> {
+        let entries = self.store.entries().await?;
+        Ok(CacheSnapshot::new(
+            entries
+                .iter()
+                .filter(|(_, entry)| entry.is_success() && !entry.is_expired(self.retention(entry)))
+                .map(|(key, entry)| entry.to_snapshot_entry(key))
+                .collect(),
+        ))
+    }
+
+    /// Load the entries of `snapshot` that can still be served. Restored
+    /// entries keep their original age and are treated as stale, so they are
+    /// revalidated before being served as hits. Entries already in the cache
+    /// are kept. Returns the number of entries restored.
+    pub async fn restore(&self, snapshot: CacheSnapshot) -> usize {
+        let mut restored = 0;
+        for entry in snapshot.entries {
+            if is_error_status(entry.status_code) || self.cached(&entry.key).await.is_some() {
+                continue;
+            }
+            let key = entry.key.clone();
+            let mut cached_response = CachedResponse::from_snapshot(entry, self.default_policy.ttl);
+            if cached_response.is_expired(self.retention(&cached_response)) {
+                continue;
+            }
+            // Snapshots keep only the identity body
+            cached_response.encodings = self.compress(&cached_response.data).await;
+            self.store_entry(&key, cached_response).await;
+            restored += 1;
+        }
+        restored
+    }
+
+    /// Write the cacheable entries to `path`. Returns the number of entries saved.
+    pub async fn save_snapshot(&self, path: &Path) -> Result<usize, String> {
+        let snapshot = self.snapshot().await?;
+        snapshot.write_to(path).await?;
+        Ok(snapshot.entries.len())
+    }
+
+    /// Restore the snapshot at `path`, if there is one. Returns the number of
+    /// entries restored.
+    pub async fn load_snapshot(&self, path: &Path) -> Result<usize, String> {
+        match CacheSnapshot::read_from(path).await? {
+            Some(snapshot) => Ok(self.restore(snapshot).await),
+            None => {
+                info!("No cache snapshot found at {}", path.display());
+                Ok(0)
+            }
+        }
+    }
+
+    /// Save a snapshot to `path` every `interval` (never when zero) and once
+    /// more when `shutdown` changes, then stop.
+    pub fn spawn_snapshotter(
+        &self,
+        path: PathBuf,
+        interval: Duration,
+        mut shutdown: watch::Receiver<bool>,
+    ) -> JoinHandle<()> {
+        let this = self.clone();
+        tokio::spawn(async move {
+            let periodic = async {
+                if interval.is_zero() {
+                    return std::future::pending().await;
+                }
+                let mut ticker = tokio::time::interval(interval);
+                // The first tick completes immediately
+                ticker.tick().await;
+                loop {
+                    ticker.tick().await;
+                    match this.save_snapshot(&path).await {
+                        Ok(saved) => info!("Saved {} cache entries to {}", saved, path.display()),
+                        Err(e) => error!("{}", e),
+                    }
+                }
+            };
+            tokio::select! {
+                _ = periodic => {}
+                _ = shutdown.changed() => {}
+            }
+
+            match this.save_snapshot(&path).await {
+                Ok(saved) => info!(
+                    "Saved {} cache entries to {} on shutdown",
+                    saved,
+                    path.display()
+                ),
+                Err(e) => error!("{}", e),
+            }
+        })
     }
 }
 
-pub async fn get_latest_tag(repo_url: &str) -> Option<(String, String)> {
+/// Latest release tag of a GitHub repository and the URL of its release page.
+/// Lookups go through `cache`, so GitHub is asked at most once per `policy.ttl`.
+pub async fn get_latest_tag(
+    cache: &ApiCache,
+    repo_url: &str,
+    policy: CachePolicy,
+) -> Option<(String, String)> {
     // Extract owner and repo from GitHub URL
     let url_parts: Vec<&str> = repo_url.trim_end_matches('/').split('/').collect();
     if url_parts.len() < 5 || url_parts[2] != "github.com" {
@@ -242,32 +1699,43 @@ pub async fn get_latest_tag(repo_url: &str) -> Option<(String, String)> {
         owner, repo
     );
 
-    let client = reqwest::Client::builder()
-        .user_agent("rwrs-server")
-        .timeout(Duration::from_secs(10))
-        .build()
-        .ok()?;
+    let tag_name = latest_release_tag(cache, &api_url, policy).await?;
+    // Return URL to the release page instead of zipball
+    let release_url = format!(
+        "https://github.com/{}/{}/releases/tag/{}",
+        owner, repo, tag_name
+    );
+    Some((tag_name, release_url))
+}
 
-    match client.get(&api_url).send().await {
-        Ok(response) => {
-            if response.status().is_success()
-                && let Ok(release_info) = response.json::<serde_json::Value>().await
-            {
-                let tag_name = release_info.get("tag_name")?.as_str()?.to_string();
-                // Return URL to the release page instead of zipball
-                let release_url = format!(
-                    "https://github.com/{}/{}/releases/tag/{}",
-                    owner, repo, tag_name
-                );
-                return Some((tag_name, release_url));
-            }
-        }
+/// `tag_name` of the release returned by the GitHub API at `api_url`.
+pub(crate) async fn latest_release_tag(
+    cache: &ApiCache,
+    api_url: &str,
+    policy: CachePolicy,
+) -> Option<String> {
+    let lookup = match cache.lookup_with_policy(api_url, policy).await {
+        Ok(lookup) => lookup,
         Err(e) => {
             error!("Failed to fetch release info from {}: {}", api_url, e);
+            return None;
         }
+    };
+    if !(200..300).contains(&lookup.status_code) {
+        error!(
+            "Failed to fetch release info from {}: status {}",
+            api_url, lookup.status_code
+        );
+        return None;
     }
 
-    None
+    match serde_json::from_str::<serde_json::Value>(&lookup.data) {
+        Ok(release_info) => Some(release_info.get("tag_name")?.as_str()?.to_string()),
+        Err(e) => {
+            error!("Failed to parse release info from {}: {}", api_url, e);
+            None
+        }
+    }
 }
 
 #[cfg(test)]
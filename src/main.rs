@@ -1,139 +1,73 @@
-use salvo::affix_state;
 use salvo::prelude::*;
-use salvo::serve_static::StaticDir;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use tracing::{error, info};
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::{error, info, warn};
 
 // Import from lib.rs
-use rwrs_server::{ApiCache, Config, MapsConfig, RepoVersion, VersionInfo, get_latest_tag};
+use rwrs_server::handlers::{AppState, create_router};
+use rwrs_server::{
+    ApiCache, CacheBackend, CachePoller, Config, MapsConfig, PopularQueries, ServerSnapshots,
+    UpstreamClient,
+};
 
-#[handler]
-async fn ping() -> &'static str {
-    "pong"
-}
-
-#[handler]
-async fn maps_handler(depot: &mut Depot, res: &mut Response) {
-    let maps_config = depot.obtain::<Arc<MapsConfig>>().unwrap();
-    let maps = maps_config.get_maps();
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::fmt().init();
 
-    res.render(Json(&maps));
-}
+    let config = Config::new().unwrap();
+    let listen_addr = format!("{}:{}", config.host, config.port);
+    info!("listening at {}", listen_addr);
 
-#[handler]
-async fn version_handler(depot: &mut Depot, res: &mut Response) {
-    let config = depot.obtain::<Arc<Config>>().unwrap();
-
-    let android_version = if let Some(ref android_repo_url) = config.android_repo_url {
-        match get_latest_tag(android_repo_url).await {
-            Some((version, url)) => RepoVersion {
-                version: Some(version),
-                url: Some(url),
-            },
-            None => RepoVersion {
-                version: None,
-                url: None,
-            },
-        }
-    } else {
-        RepoVersion {
-            version: None,
-            url: None,
+    // Shared HTTP client for all upstream requests
+    let upstream_client = match UpstreamClient::new(config.upstream_config()) {
+        Ok(client) => client,
+        Err(e) => {
+            error!("{}", e);
+            return;
         }
     };
 
-    let web_version = if let Some(ref web_repo_url) = config.web_repo_url {
-        match get_latest_tag(web_repo_url).await {
-            Some((version, url)) => RepoVersion {
-                version: Some(version),
-                url: Some(url),
-            },
-            None => RepoVersion {
-                version: None,
-                url: None,
-            },
-        }
-    } else {
-        RepoVersion {
-            version: None,
-            url: None,
+    let cache_store = match config.cache_store() {
+        Ok(store) => store,
+        Err(e) => {
+            error!("{}", e);
+            return;
         }
     };
 
-    let version_info = VersionInfo {
-        android: android_version,
-        web: web_version,
-    };
-
-    res.render(Json(&version_info));
-}
-
-#[handler]
-async fn servers_handler(req: &mut Request, depot: &mut Depot, res: &mut Response) {
-    // Get cache from depot
-    let cache = depot.obtain::<Arc<ApiCache>>().unwrap();
-
-    // Get query string from original request
-    let query_string = req.uri().query().unwrap_or("");
-    let base_url = "http://rwr.runningwithrifles.com/rwr_server_list/get_server_list.php";
-    let url = if query_string.is_empty() {
-        base_url.to_string()
-    } else {
-        format!("{}?{}", base_url, query_string)
-    };
-
-    match cache.get_cached_response(&url).await {
-        Ok((data, status_code)) => {
-            // Cache successful response
-            res.status_code(StatusCode::from_u16(status_code).unwrap_or(StatusCode::OK));
-            res.render(Text::Html(data));
-        }
-        Err(e) => {
-            error!("Failed to get server list: {}", e);
-            res.status_code(StatusCode::INTERNAL_SERVER_ERROR);
-            res.render(Text::Plain(format!("Unable to fetch server list: {}", e)));
-        }
+    // Create cache instance
+    let cache = Arc::new(
+        ApiCache::new(config.cache_duration_secs)
+            .with_client(upstream_client)
+            .with_circuit_breaker(config.circuit_breaker_config())
+            .with_rate_limit(config.rate_limit_config())
+            .with_stale_window(config.cache_stale_secs)
+            .with_stale_if_error(config.cache_stale_if_error_secs)
+            .with_compression(config.cache_compression)
+            .with_store(cache_store),
+    );
+    if config.cache_sweep_interval_secs > 0 {
+        cache.spawn_sweeper(Duration::from_secs(config.cache_sweep_interval_secs));
     }
-}
 
-#[handler]
-async fn players_handler(req: &mut Request, depot: &mut Depot, res: &mut Response) {
-    // Get cache from depot
-    let cache = depot.obtain::<Arc<ApiCache>>().unwrap();
-
-    // Get query string from original request
-    let query_string = req.uri().query().unwrap_or("");
-    let base_url = "http://rwr.runningwithrifles.com/rwr_stats/view_players.php";
-    let url = if query_string.is_empty() {
-        base_url.to_string()
-    } else {
-        format!("{}?{}", base_url, query_string)
-    };
+    // Background tasks watch this channel and stop once the server shuts down
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let mut background_tasks = Vec::new();
 
-    match cache.get_cached_response(&url).await {
-        Ok((data, status_code)) => {
-            // Cache successful response
-            res.status_code(StatusCode::from_u16(status_code).unwrap_or(StatusCode::OK));
-            res.render(Text::Html(data));
-        }
-        Err(e) => {
-            error!("Failed to get players data: {}", e);
-            res.status_code(StatusCode::INTERNAL_SERVER_ERROR);
-            res.render(Text::Plain(format!("Unable to fetch players data: {}", e)));
+    // Start warm from the snapshot written by the previous run
+    if let Some(ref path) = config.cache_snapshot_path {
+        match cache.load_snapshot(Path::new(path)).await {
+            Ok(restored) => info!("Restored {} cache entries from {}", restored, path),
+            Err(e) => warn!("{}. Starting with an empty cache.", e),
         }
+        background_tasks.push(cache.spawn_snapshotter(
+            PathBuf::from(path),
+            Duration::from_secs(config.cache_snapshot_interval_secs),
+            shutdown_rx.clone(),
+        ));
     }
-}
-
-#[tokio::main]
-async fn main() {
-    tracing_subscriber::fmt().init();
-
-    let config = Config::new().unwrap();
-    let listen_addr = format!("{}:{}", config.host, config.port);
-    info!("listening at {}", listen_addr);
-
-    // Create cache instance
-    let cache = Arc::new(ApiCache::new(config.cache_duration_secs));
 
     // Load maps configuration
     info!(
@@ -156,7 +90,87 @@ async fn main() {
         "  - Cache expiry time: {} seconds",
         config.cache_duration_secs
     );
+    info!(
+        "  - Route TTLs: server list {} seconds, player list {} seconds, GitHub versions {} seconds",
+        config.server_list_cache_secs, config.player_list_cache_secs, config.version_cache_secs
+    );
+    info!(
+        "  - Stale-while-revalidate window: {} seconds",
+        config.cache_stale_secs
+    );
+    info!(
+        "  - Stale-if-error window: {} seconds",
+        config.cache_stale_if_error_secs
+    );
+    info!(
+        "  - Negative cache TTL: server list {} seconds, player list {} seconds, GitHub versions {} seconds",
+        config.server_list_negative_cache_secs,
+        config.player_list_negative_cache_secs,
+        config.version_negative_cache_secs
+    );
+    info!(
+        "  - Pre-compressed responses (gzip, brotli): {}",
+        if config.cache_compression {
+            "enabled"
+        } else {
+            "disabled"
+        }
+    );
+    match config.cache_backend {
+        CacheBackend::Memory => info!(
+            "  - Cache store: memory, limited to {} entries, {} bytes (0 = unlimited)",
+            config.cache_max_entries, config.cache_max_bytes
+        ),
+        CacheBackend::Redis => info!(
+            "  - Cache store: redis at {}, key prefix {}",
+            config.cache_redis_url, config.cache_redis_prefix
+        ),
+        CacheBackend::Disk => info!("  - Cache store: disk at {}", config.cache_disk_path),
+    }
+    info!(
+        "  - Cache sweep interval: {} seconds",
+        config.cache_sweep_interval_secs
+    );
+    if let Some(ref path) = config.cache_snapshot_path {
+        info!(
+            "  - Cache snapshot: {} every {} seconds and on shutdown",
+            path, config.cache_snapshot_interval_secs
+        );
+    }
     info!("  - Maps config file: {}", config.maps_config_path);
+    info!(
+        "  - Upstream timeouts: connect {}s, read {}s, total {}s",
+        config.upstream_connect_timeout_secs,
+        config.upstream_read_timeout_secs,
+        config.upstream_timeout_secs
+    );
+    info!(
+        "  - Upstream retries: {} with {} ms base backoff",
+        config.upstream_max_retries, config.upstream_retry_backoff_ms
+    );
+    info!(
+        "  - Upstream max response size: {} bytes",
+        config.upstream_max_response_bytes
+    );
+    info!(
+        "  - Upstream budget: {} requests/s, burst {}, {} concurrent, {} ms max wait (0 = unlimited)",
+        config.upstream_rate_limit,
+        config.upstream_rate_limit_burst,
+        config.upstream_max_concurrent,
+        config.upstream_rate_limit_wait_ms
+    );
+    info!(
+        "  - Circuit breaker: opens after {} failures, {} seconds cool-down (0 = disabled)",
+        config.breaker_failure_threshold, config.breaker_cool_down_secs
+    );
+    info!(
+        "  - Admin API: {}",
+        if config.admin_token.is_some() {
+            "enabled"
+        } else {
+            "disabled (set ADMIN_TOKEN)"
+        }
+    );
     if let Some(ref url) = config.android_repo_url {
         info!("  - Android repo URL: {}", url);
     }
@@ -164,39 +178,87 @@ async fn main() {
         info!("  - Web repo URL: {}", url);
     }
 
-    // Create config for sharing
-    let config = Arc::new(config);
-
-    let router = Router::new()
-        .hoop(RequestId::new())
-        .push(Router::new().path("/ping").get(ping))
-        .push(
-            Router::new()
-                .path("/api/version")
-                .hoop(affix_state::inject(config.clone()))
-                .get(version_handler),
-        )
-        .push(
-            Router::new()
-                .path("/api/maps")
-                .hoop(affix_state::inject(maps_config.clone()))
-                .get(maps_handler),
-        )
-        .push(
-            Router::new()
-                .path("/api/server_list")
-                .hoop(affix_state::inject(cache.clone()))
-                .goal(servers_handler),
-        )
-        .push(
-            Router::new()
-                .path("/api/player_list")
-                .hoop(affix_state::inject(cache.clone()))
-                .goal(players_handler),
-        )
-        .push(Router::with_path("{**path}").get(StaticDir::new(["static"]).defaults("index.html")));
+    info!(
+        "  - Merged server list: pages of {} servers, at most {} pages",
+        config.server_list_page_size, config.server_list_max_pages
+    );
+
+    // Share state with the handlers
+    let server_snapshots = Arc::new(ServerSnapshots::new(
+        cache.clone(),
+        config.server_list_route(),
+        config.server_snapshot_config(),
+    ));
+    let state = AppState {
+        config: Arc::new(config),
+        cache,
+        maps_config,
+        popular_server_queries: Arc::new(PopularQueries::new()),
+        server_snapshots,
+    };
+    let router = create_router(&state);
+
+    if let Some(poller_config) = state.config.poller_config() {
+        info!(
+            "Server list poller enabled: every {} seconds, queries {:?}, {} learned queries",
+            poller_config.interval.as_secs(),
+            poller_config.queries,
+            poller_config.learned_queries
+        );
+        let poller = CachePoller::new(
+            state.cache.clone(),
+            state.config.server_list_route(),
+            poller_config,
+            state.popular_server_queries.clone(),
+        );
+        background_tasks.push(poller.spawn(shutdown_rx.clone()));
+    }
 
     let service = Service::new(router).hoop(Logger::new());
     let acceptor = TcpListener::new(listen_addr).bind().await;
-    Server::new(acceptor).serve(service).await;
+    let server = Server::new(acceptor);
+    let handle = server.handle();
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        info!("Shutdown signal received, stopping server");
+        handle.stop_graceful(None);
+    });
+    server.serve(service).await;
+
+    let _ = shutdown_tx.send(true);
+    for task in background_tasks {
+        let _ = task.await;
+    }
+    info!("Server stopped");
+}
+
+/// Resolve on Ctrl+C, or on SIGTERM on Unix (sent by `docker stop`).
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            error!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
 }
@@ -0,0 +1,101 @@
+use serde::Serialize;
+
+use crate::GameServer;
+
+/// Longest accepted player name, in characters.
+pub const MAX_PLAYER_NAME_LEN: usize = 64;
+
+/// How closely a player name matched the searched name, best first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlayerMatchKind {
+    Exact,
+    CaseInsensitive,
+    Prefix,
+}
+
+impl PlayerMatchKind {
+    /// How `player` matches `name`, or `None` when it does not.
+    /// `lowercase_name` is `name` lowercased.
+    fn of(player: &str, name: &str, lowercase_name: &str) -> Option<Self> {
+        if player == name {
+            return Some(PlayerMatchKind::Exact);
+        }
+        let player = player.to_lowercase();
+        if player == lowercase_name {
+            Some(PlayerMatchKind::CaseInsensitive)
+        } else if player.starts_with(lowercase_name) {
+            Some(PlayerMatchKind::Prefix)
+        } else {
+            None
+        }
+    }
+}
+
+/// A player on a server whose name matched the search.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PlayerMatch {
+    pub name: String,
+    #[serde(rename = "match")]
+    pub kind: PlayerMatchKind,
+}
+
+/// A server with at least one matching player.
+#[derive(Debug, Serialize)]
+pub struct OnlinePlayerServer<'a> {
+    #[serde(flatten)]
+    pub server: &'a GameServer,
+    /// Matching players, best match first.
+    pub matched_players: Vec<PlayerMatch>,
+}
+
+/// Validate the `name` of a player search.
+pub fn parse_player_name(name: Option<&str>) -> Result<&str, String> {
+    let name = name.map(str::trim).unwrap_or_default();
+    if name.is_empty() {
+        return Err("name is required".to_string());
+    }
+    if name.chars().count() > MAX_PLAYER_NAME_LEN {
+        return Err(format!(
+            "name is longer than {} characters",
+            MAX_PLAYER_NAME_LEN
+        ));
+    }
+    Ok(name)
+}
+
+/// Servers with a player whose name is `name`, equal to it ignoring case or
+/// starting with it ignoring case. Servers with better matches come first.
+pub fn find_online_players<'a>(
+    servers: &'a [GameServer],
+    name: &str,
+) -> Vec<OnlinePlayerServer<'a>> {
+    let lowercase_name = name.to_lowercase();
+    let mut found: Vec<OnlinePlayerServer> = servers
+        .iter()
+        .filter_map(|server| {
+            let mut matched_players: Vec<PlayerMatch> = server
+                .players
+                .iter()
+                .filter_map(|player| {
+                    PlayerMatchKind::of(player, name, &lowercase_name).map(|kind| PlayerMatch {
+                        name: player.clone(),
+                        kind,
+                    })
+                })
+                .collect();
+            matched_players.sort_by(|a, b| a.kind.cmp(&b.kind).then_with(|| a.name.cmp(&b.name)));
+            (!matched_players.is_empty()).then_some(OnlinePlayerServer {
+                server,
+                matched_players,
+            })
+        })
+        .collect();
+    found.sort_by(|a, b| {
+        a.matched_players[0]
+            .kind
+            .cmp(&b.matched_players[0].kind)
+            .then_with(|| a.server.name.cmp(&b.server.name))
+    });
+    found
+}
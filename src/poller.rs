@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::{ApiCache, ProxyRoute, canonical_query};
+
+/// Upper bound on distinct queries tracked, so unique query strings sent by
+/// crawlers cannot grow the counters without bound.
+const MAX_TRACKED_QUERIES: usize = 1000;
+
+/// Counts how often each canonical query of a route is requested.
+#[derive(Debug, Default)]
+pub struct PopularQueries {
+    counts: Mutex<HashMap<String, u64>>,
+}
+
+impl PopularQueries {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record one request for an already canonical query string.
+    pub fn record(&self, query: &str) {
+        let mut counts = self.counts.lock().unwrap();
+        if let Some(count) = counts.get_mut(query) {
+            *count += 1;
+        } else if counts.len() < MAX_TRACKED_QUERIES {
+            counts.insert(query.to_string(), 1);
+        }
+    }
+
+    /// The `n` most requested queries, most popular first.
+    pub fn top(&self, n: usize) -> Vec<String> {
+        let counts = self.counts.lock().unwrap();
+        let mut queries: Vec<(&String, &u64)> = counts.iter().collect();
+        queries.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+        queries
+            .into_iter()
+            .take(n)
+            .map(|(query, _)| query.clone())
+            .collect()
+    }
+
+    /// Halve every count and forget queries that are no longer requested,
+    /// so the ranking follows recent traffic.
+    pub fn decay(&self) {
+        let mut counts = self.counts.lock().unwrap();
+        counts.retain(|_, count| {
+            *count /= 2;
+            *count > 0
+        });
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PollerConfig {
+    pub interval: Duration,
+    /// Queries refreshed on every run.
+    pub queries: Vec<String>,
+    /// Number of most requested queries refreshed in addition to `queries`.
+    pub learned_queries: usize,
+}
+
+/// Background task that refreshes the most common queries of a route on a
+/// fixed schedule, so user requests find a warm cache.
+pub struct CachePoller {
+    cache: Arc<ApiCache>,
+    route: ProxyRoute,
+    config: PollerConfig,
+    popular: Arc<PopularQueries>,
+}
+
+impl CachePoller {
+    pub fn new(
+        cache: Arc<ApiCache>,
+        route: ProxyRoute,
+        config: PollerConfig,
+        popular: Arc<PopularQueries>,
+    ) -> Self {
+        Self {
+            cache,
+            route,
+            config,
+            popular,
+        }
+    }
+
+    /// Canonical queries refreshed by the next run, configured ones first.
+    pub fn queries(&self) -> Vec<String> {
+        let mut queries: Vec<String> = Vec::new();
+        let configured = self
+            .config
+            .queries
+            .iter()
+            .map(|query| canonical_query(query, self.route.params));
+        let learned = self.popular.top(self.config.learned_queries);
+        for query in configured.chain(learned) {
+            if !queries.contains(&query) {
+                queries.push(query);
+            }
+        }
+        queries
+    }
+
+    /// Refresh every query once. Returns the number of successful refreshes.
+    pub async fn poll_once(&self) -> usize {
+        let mut refreshed = 0;
+        for query in self.queries() {
+            match self.cache.refresh_route(&self.route, &query).await {
+                Ok((_, status_code)) if status_code < 400 => refreshed += 1,
+                Ok((_, status_code)) => warn!(
+                    "Poller got status {} refreshing {}",
+                    status_code,
+                    self.route.cache_key(&query)
+                ),
+                Err(e) => warn!(
+                    "Poller failed to refresh {}: {}",
+                    self.route.cache_key(&query),
+                    e
+                ),
+            }
+        }
+        self.popular.decay();
+        refreshed
+    }
+
+    /// Run [`CachePoller::poll_once`] every interval until `shutdown` changes.
+    pub fn spawn(self, mut shutdown: watch::Receiver<bool>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(self.config.interval);
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {
+                        let refreshed = self.poll_once().await;
+                        info!("Poller refreshed {} {} queries", refreshed, self.route.name);
+                    }
+                    _ = shutdown.changed() => {
+                        info!("Poller for {} stopped", self.route.name);
+                        break;
+                    }
+                }
+            }
+        })
+    }
+}
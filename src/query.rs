@@ -0,0 +1,113 @@
+use std::collections::BTreeMap;
+
+/// How the value of a forwarded query parameter is normalised.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamKind {
+    /// Non-negative integer, leading zeros and signs are stripped.
+    Number,
+    /// Value the upstream treats case-insensitively.
+    Lowercase,
+    /// Value forwarded exactly as received.
+    Verbatim,
+}
+
+/// A query parameter understood by an upstream endpoint.
+#[derive(Debug, Clone, Copy)]
+pub struct QueryParam {
+    pub name: &'static str,
+    pub kind: ParamKind,
+}
+
+/// Parameters accepted by `get_server_list.php`.
+pub const SERVER_LIST_PARAMS: &[QueryParam] = &[
+    QueryParam {
+        name: "start",
+        kind: ParamKind::Number,
+    },
+    QueryParam {
+        name: "size",
+        kind: ParamKind::Number,
+    },
+    QueryParam {
+        name: "names",
+        kind: ParamKind::Number,
+    },
+];
+
+/// Parameters accepted by `view_players.php`.
+pub const PLAYER_LIST_PARAMS: &[QueryParam] = &[
+    QueryParam {
+        name: "db",
+        kind: ParamKind::Lowercase,
+    },
+    QueryParam {
+        name: "sort",
+        kind: ParamKind::Lowercase,
+    },
+    QueryParam {
+        name: "order",
+        kind: ParamKind::Lowercase,
+    },
+    QueryParam {
+        name: "start",
+        kind: ParamKind::Number,
+    },
+    QueryParam {
+        name: "size",
+        kind: ParamKind::Number,
+    },
+    QueryParam {
+        name: "search",
+        kind: ParamKind::Verbatim,
+    },
+    QueryParam {
+        name: "selected",
+        kind: ParamKind::Verbatim,
+    },
+];
+
+/// Normalise a raw query string into a canonical form.
+///
+/// Only parameters listed in `params` are kept, names are matched exactly
+/// (PHP's `$_GET` keys are case-sensitive), empty or invalid values are
+/// dropped, the last occurrence of a repeated parameter wins (as in PHP) and
+/// the result is sorted by name, so equivalent requests produce the same
+/// string.
+pub fn canonical_query(query: &str, params: &[QueryParam]) -> String {
+    let mut values = BTreeMap::new();
+
+    for (name, value) in form_urlencoded::parse(query.as_bytes()) {
+        let Some(param) = params.iter().find(|param| param.name == name) else {
+            continue;
+        };
+
+        let value = value.trim();
+        let normalised = match param.kind {
+            ParamKind::Number => value.parse::<u64>().ok().map(|n| n.to_string()),
+            ParamKind::Lowercase => Some(value.to_lowercase()),
+            ParamKind::Verbatim => Some(value.to_string()),
+        };
+
+        match normalised {
+            Some(value) if !value.is_empty() => {
+                values.insert(param.name, value);
+            }
+            _ => {
+                values.remove(param.name);
+            }
+        }
+    }
+
+    form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(values)
+        .finish()
+}
+
+/// Append `query` to `base_url`, omitting the `?` when the query is empty.
+pub fn build_url(base_url: &str, query: &str) -> String {
+    if query.is_empty() {
+        base_url.to_string()
+    } else {
+        format!("{}?{}", base_url, query)
+    }
+}
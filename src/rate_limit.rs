@@ -0,0 +1,183 @@
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Settings of the limiter shared by every upstream request of an
+/// [`crate::ApiCache`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimitConfig {
+    /// Requests per second refilling the token bucket. Zero disables the limit.
+    pub requests_per_sec: f64,
+    /// Requests that may be sent back to back after an idle period.
+    pub burst: u32,
+    /// Upstream requests running at the same time. Zero disables the cap.
+    pub max_concurrent: usize,
+    /// How long a request may wait for its turn before it is rejected.
+    pub max_wait: Duration,
+}
+
+impl RateLimitConfig {
+    pub fn new(requests_per_sec: f64, burst: u32, max_concurrent: usize, max_wait_ms: u64) -> Self {
+        Self {
+            requests_per_sec,
+            burst,
+            max_concurrent,
+            max_wait: Duration::from_millis(max_wait_ms),
+        }
+    }
+
+    pub fn disabled() -> Self {
+        Self::new(0.0, 0, 0, 0)
+    }
+
+    pub fn is_rate_limited(&self) -> bool {
+        self.requests_per_sec > 0.0
+    }
+}
+
+/// Snapshot of the limiter for the status endpoint.
+#[derive(Debug, Clone, Serialize)]
+pub struct RateLimitStatus {
+    pub requests_per_sec: f64,
+    pub burst: u32,
+    /// Tokens left in the bucket, negative while requests wait for a token.
+    pub available_tokens: f64,
+    pub max_concurrent: usize,
+    pub in_flight: usize,
+    /// Requests rejected since startup because the budget was exhausted.
+    pub rejected: u64,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+/// Token bucket and concurrency cap for upstream requests. Clones share
+/// their budget.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    bucket: Arc<Mutex<Bucket>>,
+    slots: Option<Arc<Semaphore>>,
+    rejected: Arc<AtomicU64>,
+}
+
+/// Permission to send one upstream request, holding its concurrency slot
+/// until dropped.
+#[derive(Debug)]
+pub struct UpstreamPermit {
+    _slot: Option<OwnedSemaphorePermit>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            bucket: Arc::new(Mutex::new(Bucket {
+                tokens: f64::from(config.burst.max(1)),
+                refilled_at: Instant::now(),
+            })),
+            slots: (config.max_concurrent > 0)
+                .then(|| Arc::new(Semaphore::new(config.max_concurrent))),
+            rejected: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Wait for a token and a free concurrency slot. Fails without waiting
+    /// when the token would take longer than the configured maximum wait, and
+    /// when no slot frees up within what is left of it.
+    pub async fn acquire(&self) -> Result<UpstreamPermit, String> {
+        let started = Instant::now();
+        let Some(wait) = self.reserve_token() else {
+            self.rejected.fetch_add(1, Ordering::Relaxed);
+            return Err(format!(
+                "Upstream rate limit of {} requests per second exceeded",
+                self.config.requests_per_sec
+            ));
+        };
+        tokio::time::sleep(wait).await;
+
+        let Some(slots) = &self.slots else {
+            return Ok(UpstreamPermit { _slot: None });
+        };
+        let remaining = self.config.max_wait.saturating_sub(started.elapsed());
+        match tokio::time::timeout(remaining, slots.clone().acquire_owned()).await {
+            Ok(Ok(slot)) => Ok(UpstreamPermit { _slot: Some(slot) }),
+            _ => {
+                // Nothing is sent, so the token goes back to the bucket
+                self.refund_token();
+                self.rejected.fetch_add(1, Ordering::Relaxed);
+                Err(format!(
+                    "{} upstream requests already in flight",
+                    self.config.max_concurrent
+                ))
+            }
+        }
+    }
+
+    /// Take a token and return how long to wait until it is due, `None` when
+    /// that is longer than the maximum wait. Tokens of waiting requests are
+    /// taken in advance, so the bucket goes negative while requests queue.
+    fn reserve_token(&self) -> Option<Duration> {
+        if !self.config.is_rate_limited() {
+            return Some(Duration::ZERO);
+        }
+
+        let mut bucket = self.bucket.lock().unwrap();
+        self.refill(&mut bucket);
+        let missing = 1.0 - bucket.tokens;
+        let wait = if missing > 0.0 {
+            // Too long to represent is longer than any maximum wait
+            Duration::try_from_secs_f64(missing / self.config.requests_per_sec).ok()?
+        } else {
+            Duration::ZERO
+        };
+        if wait > self.config.max_wait {
+            return None;
+        }
+        bucket.tokens -= 1.0;
+        Some(wait)
+    }
+
+    /// Return a token taken by [`RateLimiter::reserve_token`] that was not used.
+    fn refund_token(&self) {
+        if !self.config.is_rate_limited() {
+            return;
+        }
+        let mut bucket = self.bucket.lock().unwrap();
+        self.refill(&mut bucket);
+        bucket.tokens = (bucket.tokens + 1.0).min(f64::from(self.config.burst.max(1)));
+    }
+
+    fn refill(&self, bucket: &mut Bucket) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.config.requests_per_sec)
+            .min(f64::from(self.config.burst.max(1)));
+        bucket.refilled_at = now;
+    }
+
+    pub fn status(&self) -> RateLimitStatus {
+        let available_tokens = if self.config.is_rate_limited() {
+            let mut bucket = self.bucket.lock().unwrap();
+            self.refill(&mut bucket);
+            bucket.tokens
+        } else {
+            0.0
+        };
+        RateLimitStatus {
+            requests_per_sec: self.config.requests_per_sec,
+            burst: self.config.burst,
+            available_tokens,
+            max_concurrent: self.config.max_concurrent,
+            in_flight: self.slots.as_ref().map_or(0, |slots| {
+                self.config.max_concurrent - slots.available_permits()
+            }),
+            rejected: self.rejected.load(Ordering::Relaxed),
+        }
+    }
+}
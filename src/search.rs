@@ -0,0 +1,429 @@
+use serde::Serialize;
+use std::fmt;
+
+use crate::GameServer;
+
+/// Longest accepted search expression, in characters.
+const MAX_SEARCH_LEN: usize = 512;
+
+const FIELDS: &str = "name, mode, map, version, realm, comment, player, address, players, \
+                      max_players, free, bots, dedicated";
+
+/// Why a server list query was rejected, sent as the body of a 400 response.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct QueryError {
+    pub error: String,
+    /// Character offset in the search expression where parsing failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<usize>,
+    /// What the parser expected at `position`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected: Option<String>,
+}
+
+impl QueryError {
+    fn at(position: usize, error: String, expected: &str) -> Self {
+        Self {
+            error,
+            position: Some(position),
+            expected: Some(expected.to_string()),
+        }
+    }
+}
+
+impl From<String> for QueryError {
+    fn from(error: String) -> Self {
+        Self {
+            error,
+            position: None,
+            expected: None,
+        }
+    }
+}
+
+impl From<&str> for QueryError {
+    fn from(error: &str) -> Self {
+        Self::from(error.to_string())
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.position {
+            Some(position) => write!(f, "{} at position {}", self.error, position),
+            None => f.write_str(&self.error),
+        }
+    }
+}
+
+/// A parsed search expression such as
+/// `mode:coop players>=10 map:map9 -name:test version:1.9*`.
+///
+/// Terms are separated by whitespace and must all match. A term is
+/// `field:value`, a comparison like `players>=10`, or a bare word matched
+/// against the server name; `-` in front of a term negates it. Text values
+/// may be quoted and `*` matches any run of characters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchQuery {
+    terms: Vec<Term>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Term {
+    negated: bool,
+    condition: Condition,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Condition {
+    Text(TextField, String),
+    Number(NumberField, Comparison, u32),
+    Dedicated(bool),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TextField {
+    Name,
+    Mode,
+    Map,
+    Version,
+    Realm,
+    Comment,
+    Player,
+    Address,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NumberField {
+    Players,
+    MaxPlayers,
+    FreeSlots,
+    Bots,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Equal,
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+}
+
+enum Field {
+    Text(TextField),
+    Number(NumberField),
+    Dedicated,
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_lowercase().as_str() {
+            "name" => Field::Text(TextField::Name),
+            "mode" => Field::Text(TextField::Mode),
+            "map" => Field::Text(TextField::Map),
+            "version" => Field::Text(TextField::Version),
+            "realm" => Field::Text(TextField::Realm),
+            "comment" => Field::Text(TextField::Comment),
+            "player" => Field::Text(TextField::Player),
+            "address" => Field::Text(TextField::Address),
+            "players" => Field::Number(NumberField::Players),
+            "max_players" => Field::Number(NumberField::MaxPlayers),
+            "free" => Field::Number(NumberField::FreeSlots),
+            "bots" => Field::Number(NumberField::Bots),
+            "dedicated" => Field::Dedicated,
+            _ => return None,
+        })
+    }
+}
+
+impl SearchQuery {
+    pub fn parse(input: &str) -> Result<Self, QueryError> {
+        let chars: Vec<char> = input.chars().collect();
+        if chars.len() > MAX_SEARCH_LEN {
+            return Err(format!("search is longer than {} characters", MAX_SEARCH_LEN).into());
+        }
+
+        let mut parser = Parser { chars, pos: 0 };
+        let mut terms = Vec::new();
+        loop {
+            parser.skip_whitespace();
+            if parser.at_end() {
+                break;
+            }
+            terms.push(parser.term()?);
+        }
+        Ok(Self { terms })
+    }
+
+    pub fn matches(&self, server: &GameServer) -> bool {
+        self.terms
+            .iter()
+            .all(|term| term.condition.matches(server) != term.negated)
+    }
+}
+
+impl Condition {
+    fn matches(&self, server: &GameServer) -> bool {
+        match self {
+            Condition::Text(field, pattern) => match field {
+                TextField::Name => glob_match(pattern, &server.name, true),
+                TextField::Comment => glob_match(pattern, &server.comment, true),
+                TextField::Mode => glob_match(pattern, &server.mode, false),
+                TextField::Version => glob_match(pattern, &server.version, false),
+                TextField::Realm => glob_match(pattern, &server.realm, false),
+                TextField::Address => glob_match(pattern, &server.address, false),
+                // The full map path, its last segment or the map's name
+                TextField::Map => {
+                    glob_match(pattern, &server.map_id, false)
+                        || server
+                            .map_id
+                            .rsplit('/')
+                            .next()
+                            .is_some_and(|map| glob_match(pattern, map, false))
+                        || glob_match(pattern, &server.map_name, false)
+                }
+                TextField::Player => server
+                    .players
+                    .iter()
+                    .any(|player| glob_match(pattern, player, false)),
+            },
+            Condition::Number(field, comparison, value) => {
+                let actual = match field {
+                    NumberField::Players => server.current_players,
+                    NumberField::MaxPlayers => server.max_players,
+                    NumberField::FreeSlots => {
+                        server.max_players.saturating_sub(server.current_players)
+                    }
+                    NumberField::Bots => server.bots,
+                };
+                match comparison {
+                    Comparison::Equal => actual == *value,
+                    Comparison::Greater => actual > *value,
+                    Comparison::GreaterOrEqual => actual >= *value,
+                    Comparison::Less => actual < *value,
+                    Comparison::LessOrEqual => actual <= *value,
+                }
+            }
+            Condition::Dedicated(dedicated) => server.dedicated == *dedicated,
+        }
+    }
+}
+
+/// Case-insensitive match of a lowercase `pattern` in which `*` matches any
+/// run of characters. With `anywhere` the pattern may match any part of
+/// `text`, otherwise it has to match all of it.
+fn glob_match(pattern: &str, text: &str, anywhere: bool) -> bool {
+    let text = text.to_lowercase();
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if parts.len() == 1 {
+        return if anywhere {
+            text.contains(first)
+        } else {
+            text == first
+        };
+    }
+
+    let mut rest = text.as_str();
+    match rest.find(first) {
+        Some(index) if anywhere || index == 0 => rest = &rest[index + first.len()..],
+        _ => return false,
+    }
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    if anywhere {
+        rest.contains(last)
+    } else {
+        rest.ends_with(last)
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn at_end(&self) -> bool {
+        self.pos >= self.chars.len()
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(&predicate) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    fn term(&mut self) -> Result<Term, QueryError> {
+        let negated = self.peek() == Some('-');
+        if negated {
+            self.pos += 1;
+            if self.at_end() || self.peek().is_some_and(char::is_whitespace) {
+                return Err(QueryError::at(
+                    self.pos,
+                    "expected a term after '-'".to_string(),
+                    "a field or a word",
+                ));
+            }
+        }
+
+        let field_start = self.pos;
+        let name = self.take_while(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !self
+            .peek()
+            .is_some_and(|c| matches!(c, ':' | '=' | '<' | '>'))
+        {
+            // A bare word or quoted text, matched against the name
+            self.pos = field_start;
+            let pattern = self.value()?;
+            return Ok(Term {
+                negated,
+                condition: Condition::Text(TextField::Name, pattern),
+            });
+        }
+        if name.is_empty() {
+            return Err(QueryError::at(
+                field_start,
+                format!(
+                    "expected a field name before '{}'",
+                    self.peek().unwrap_or(' ')
+                ),
+                FIELDS,
+            ));
+        }
+        let field = Field::parse(&name).ok_or_else(|| {
+            QueryError::at(field_start, format!("unknown field '{}'", name), FIELDS)
+        })?;
+
+        let operator_start = self.pos;
+        let comparison = self.comparison();
+        let condition = match field {
+            Field::Text(field) => {
+                if !matches!(comparison, Comparison::Equal) {
+                    return Err(QueryError::at(
+                        operator_start,
+                        format!("'{}' is a text field and cannot be compared", name),
+                        "':' or '='",
+                    ));
+                }
+                Condition::Text(field, self.value()?)
+            }
+            Field::Number(field) => {
+                let value_start = self.pos;
+                let value = self.value()?;
+                let number = value.parse().map_err(|_| {
+                    QueryError::at(
+                        value_start,
+                        format!("invalid number '{}' for '{}'", value, name),
+                        "a non-negative number",
+                    )
+                })?;
+                Condition::Number(field, comparison, number)
+            }
+            Field::Dedicated => {
+                if !matches!(comparison, Comparison::Equal) {
+                    return Err(QueryError::at(
+                        operator_start,
+                        "'dedicated' cannot be compared".to_string(),
+                        "':' or '='",
+                    ));
+                }
+                let value_start = self.pos;
+                let value = self.value()?;
+                let dedicated = match value.as_str() {
+                    "true" | "yes" | "1" => true,
+                    "false" | "no" | "0" => false,
+                    _ => {
+                        return Err(QueryError::at(
+                            value_start,
+                            format!("invalid value '{}' for 'dedicated'", value),
+                            "true or false",
+                        ));
+                    }
+                };
+                Condition::Dedicated(dedicated)
+            }
+        };
+        Ok(Term { negated, condition })
+    }
+
+    fn comparison(&mut self) -> Comparison {
+        let first = self.peek();
+        self.pos += 1;
+        let or_equal = self.peek() == Some('=');
+        match first {
+            Some('>') if or_equal => {
+                self.pos += 1;
+                Comparison::GreaterOrEqual
+            }
+            Some('<') if or_equal => {
+                self.pos += 1;
+                Comparison::LessOrEqual
+            }
+            Some('>') => Comparison::Greater,
+            Some('<') => Comparison::Less,
+            _ => Comparison::Equal,
+        }
+    }
+
+    /// A quoted or bare value, lowercased for case-insensitive matching.
+    fn value(&mut self) -> Result<String, QueryError> {
+        let start = self.pos;
+        let value = if self.peek() == Some('"') {
+            self.pos += 1;
+            let mut value = String::new();
+            loop {
+                match self.peek() {
+                    None => {
+                        return Err(QueryError::at(
+                            self.pos,
+                            format!("unterminated quote opened at position {}", start),
+                            "'\"'",
+                        ));
+                    }
+                    Some('"') => {
+                        self.pos += 1;
+                        break;
+                    }
+                    Some('\\') if self.chars.get(self.pos + 1).is_some() => {
+                        value.push(self.chars[self.pos + 1]);
+                        self.pos += 2;
+                    }
+                    Some(c) => {
+                        value.push(c);
+                        self.pos += 1;
+                    }
+                }
+            }
+            value
+        } else {
+            self.take_while(|c| !c.is_whitespace())
+        };
+
+        if value.is_empty() {
+            return Err(QueryError::at(
+                start,
+                "expected a value".to_string(),
+                "a word or a quoted string",
+            ));
+        }
+        Ok(value.to_lowercase())
+    }
+}
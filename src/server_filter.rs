@@ -0,0 +1,324 @@
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+use crate::{GameServer, QueryError, SearchQuery};
+
+/// Servers per page when the request does not set `limit`.
+pub const DEFAULT_PAGE_LIMIT: usize = 100;
+/// Largest accepted `limit`.
+pub const MAX_PAGE_LIMIT: usize = 500;
+/// Longest accepted `name_regex`, in bytes.
+const MAX_REGEX_LEN: usize = 256;
+/// Compiled size limit of `name_regex`, so a request cannot build a huge
+/// automaton.
+const REGEX_SIZE_LIMIT: usize = 1 << 20;
+
+/// Conditions a server must meet to be listed. Unset conditions match every
+/// server.
+#[derive(Debug, Clone, Default)]
+pub struct ServerFilter {
+    /// Game mode, compared case-insensitively.
+    pub mode: Option<String>,
+    /// Map path, or its last segment such as `map9`, compared
+    /// case-insensitively.
+    pub map: Option<String>,
+    pub version: Option<String>,
+    pub dedicated_only: bool,
+    pub has_free_slots: bool,
+    pub min_players: Option<u32>,
+    pub max_players: Option<u32>,
+    pub exclude_empty: bool,
+    /// Substring of the name, compared case-insensitively.
+    pub name: Option<String>,
+    pub name_regex: Option<Regex>,
+    /// Search expression given as `q`.
+    pub search: Option<SearchQuery>,
+}
+
+impl ServerFilter {
+    pub fn matches(&self, server: &GameServer) -> bool {
+        self.mode
+            .as_ref()
+            .is_none_or(|mode| server.mode.eq_ignore_ascii_case(mode))
+            && self.map.as_ref().is_none_or(|map| map_matches(server, map))
+            && self
+                .version
+                .as_ref()
+                .is_none_or(|version| server.version == *version)
+            && (!self.dedicated_only || server.dedicated)
+            && (!self.has_free_slots || server.current_players < server.max_players)
+            && self
+                .min_players
+                .is_none_or(|min| server.current_players >= min)
+            && self
+                .max_players
+                .is_none_or(|max| server.current_players <= max)
+            && (!self.exclude_empty || server.current_players > 0)
+            && self
+                .name
+                .as_ref()
+                .is_none_or(|name| server.name.to_lowercase().contains(&name.to_lowercase()))
+            && self
+                .name_regex
+                .as_ref()
+                .is_none_or(|regex| regex.is_match(&server.name))
+            && self
+                .search
+                .as_ref()
+                .is_none_or(|search| search.matches(server))
+    }
+}
+
+/// Whether the map of `server` is `map`, given as a full path or as its last
+/// path segment.
+pub fn map_matches(server: &GameServer, map: &str) -> bool {
+    let map_id = server.map_id.to_lowercase();
+    let map = map.to_lowercase();
+    map_id == map || map_id.rsplit('/').next() == Some(map.as_str())
+}
+
+/// Field a server list is sorted by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServerSort {
+    Players,
+    Name,
+    Map,
+}
+
+impl ServerSort {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "players" => Some(ServerSort::Players),
+            "name" => Some(ServerSort::Name),
+            "map" => Some(ServerSort::Map),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ServerSort::Players => "players",
+            ServerSort::Name => "name",
+            ServerSort::Map => "map",
+        }
+    }
+
+    fn value(&self, server: &GameServer) -> SortValue {
+        match self {
+            ServerSort::Players => SortValue::Number(server.current_players),
+            ServerSort::Name => SortValue::Text(server.name.to_lowercase()),
+            ServerSort::Map => SortValue::Text(server.map_name.to_lowercase()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(untagged)]
+enum SortValue {
+    Number(u32),
+    Text(String),
+}
+
+/// Place of a server in the sorted list. The endpoint breaks ties, so every
+/// server has a distinct position.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+struct Position {
+    value: SortValue,
+    endpoint: String,
+}
+
+/// Position of the last server of a page. The next page starts after it, so
+/// pages stay consistent when the snapshot changes between requests.
+#[derive(Debug, Serialize, Deserialize)]
+struct Cursor {
+    sort: String,
+    descending: bool,
+    after: Position,
+}
+
+/// Filter, sort order and page of an `/api/servers` request.
+#[derive(Debug, Clone)]
+pub struct ServerQuery {
+    pub filter: ServerFilter,
+    pub sort: ServerSort,
+    pub descending: bool,
+    pub limit: usize,
+    after: Option<Position>,
+}
+
+impl Default for ServerQuery {
+    fn default() -> Self {
+        Self {
+            filter: ServerFilter::default(),
+            sort: ServerSort::Players,
+            descending: true,
+            limit: DEFAULT_PAGE_LIMIT,
+            after: None,
+        }
+    }
+}
+
+/// One page of a filtered and sorted server list.
+#[derive(Debug)]
+pub struct ServerPage<'a> {
+    pub servers: Vec<&'a GameServer>,
+    /// Servers matching the filter across all pages.
+    pub total: usize,
+    /// Cursor of the next page, `None` on the last page.
+    pub next_cursor: Option<String>,
+}
+
+impl ServerQuery {
+    /// Parse the query string of a request. Unknown parameters are ignored,
+    /// as by the proxied routes; invalid values are errors.
+    pub fn parse(query: &str) -> Result<Self, QueryError> {
+        let mut parsed = Self::default();
+        let mut cursor = None;
+        for (name, value) in form_urlencoded::parse(query.as_bytes()) {
+            let value = value.trim();
+            let filter = &mut parsed.filter;
+            match name.as_ref() {
+                "mode" => filter.mode = non_empty(value),
+                "map" => filter.map = non_empty(value),
+                "version" => filter.version = non_empty(value),
+                "dedicated" => filter.dedicated_only = parse_flag(&name, value)?,
+                "free_slots" => filter.has_free_slots = parse_flag(&name, value)?,
+                "not_empty" => filter.exclude_empty = parse_flag(&name, value)?,
+                "min_players" => filter.min_players = Some(parse_number(&name, value)?),
+                "max_players" => filter.max_players = Some(parse_number(&name, value)?),
+                "name" => filter.name = non_empty(value),
+                "name_regex" => filter.name_regex = Some(parse_regex(value)?),
+                "q" => filter.search = Some(SearchQuery::parse(value)?),
+                "sort" => {
+                    parsed.sort = ServerSort::parse(value).ok_or_else(|| {
+                        format!("invalid sort {:?}, expected players, name or map", value)
+                    })?
+                }
+                "order" => {
+                    parsed.descending = match value.to_ascii_lowercase().as_str() {
+                        "asc" => false,
+                        "desc" => true,
+                        _ => {
+                            return Err(
+                                format!("invalid order {:?}, expected asc or desc", value).into()
+                            );
+                        }
+                    }
+                }
+                "limit" => {
+                    parsed.limit = parse_number(&name, value)?;
+                    if parsed.limit == 0 || parsed.limit > MAX_PAGE_LIMIT {
+                        return Err(
+                            format!("limit must be between 1 and {}", MAX_PAGE_LIMIT).into()
+                        );
+                    }
+                }
+                "cursor" => cursor = non_empty(value),
+                _ => {}
+            }
+        }
+
+        // Decoded last, since it must match the final sort order
+        if let Some(cursor) = cursor {
+            parsed.after = Some(parsed.decode_cursor(&cursor)?);
+        }
+        Ok(parsed)
+    }
+
+    /// Filter and sort `servers` and return the requested page.
+    pub fn apply<'a>(&self, servers: &'a [GameServer]) -> ServerPage<'a> {
+        let mut matched: Vec<(Position, &GameServer)> = servers
+            .iter()
+            .filter(|server| self.filter.matches(server))
+            .map(|server| (self.position(server), server))
+            .collect();
+        matched.sort_by(|a, b| self.compare(&a.0, &b.0));
+
+        let total = matched.len();
+        let start = match &self.after {
+            Some(after) => matched.partition_point(|(position, _)| {
+                self.compare(position, after) != Ordering::Greater
+            }),
+            None => 0,
+        };
+        let end = (start + self.limit).min(total);
+        let next_cursor = (end < total).then(|| self.encode_cursor(&matched[end - 1].0));
+        ServerPage {
+            servers: matched[start..end]
+                .iter()
+                .map(|(_, server)| *server)
+                .collect(),
+            total,
+            next_cursor,
+        }
+    }
+
+    fn position(&self, server: &GameServer) -> Position {
+        Position {
+            value: self.sort.value(server),
+            endpoint: server.endpoint(),
+        }
+    }
+
+    fn compare(&self, a: &Position, b: &Position) -> Ordering {
+        if self.descending { b.cmp(a) } else { a.cmp(b) }
+    }
+
+    fn encode_cursor(&self, after: &Position) -> String {
+        let cursor = Cursor {
+            sort: self.sort.as_str().to_string(),
+            descending: self.descending,
+            after: after.clone(),
+        };
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(&cursor).unwrap_or_default())
+    }
+
+    fn decode_cursor(&self, cursor: &str) -> Result<Position, String> {
+        let cursor: Cursor = URL_SAFE_NO_PAD
+            .decode(cursor)
+            .ok()
+            .and_then(|json| serde_json::from_slice(&json).ok())
+            .ok_or("invalid cursor")?;
+        if cursor.sort != self.sort.as_str() || cursor.descending != self.descending {
+            return Err("cursor was issued for a different sort order".to_string());
+        }
+        Ok(cursor.after)
+    }
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+fn parse_flag(name: &str, value: &str) -> Result<bool, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "" | "1" | "true" => Ok(true),
+        "0" | "false" => Ok(false),
+        _ => Err(format!(
+            "invalid {} {:?}, expected true or false",
+            name, value
+        )),
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| {
+        format!(
+            "invalid {} {:?}, expected a non-negative number",
+            name, value
+        )
+    })
+}
+
+fn parse_regex(pattern: &str) -> Result<Regex, String> {
+    if pattern.len() > MAX_REGEX_LEN {
+        return Err(format!("name_regex is longer than {} bytes", MAX_REGEX_LEN));
+    }
+    RegexBuilder::new(pattern)
+        .size_limit(REGEX_SIZE_LIMIT)
+        .build()
+        .map_err(|e| format!("invalid name_regex: {}", e))
+}
@@ -0,0 +1,132 @@
+use roxmltree::{Document, Node};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// One game server of the upstream `get_server_list.php` response.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameServer {
+    pub name: String,
+    pub address: String,
+    pub port: u16,
+    /// Path of the map, e.g. `media/packages/vanilla/maps/map9`.
+    pub map_id: String,
+    pub map_name: String,
+    pub mode: String,
+    pub realm: String,
+    pub version: String,
+    pub dedicated: bool,
+    pub bots: u32,
+    pub current_players: u32,
+    pub max_players: u32,
+    pub comment: String,
+    pub url: String,
+    /// Names of the players currently on the server.
+    pub players: Vec<String>,
+}
+
+impl GameServer {
+    /// `address:port`, which identifies a server across list pages.
+    pub fn endpoint(&self) -> String {
+        format!("{}:{}", self.address, self.port)
+    }
+}
+
+/// A `<server>` element that could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SkippedServer {
+    /// Position of the element in the list, starting at 0.
+    pub index: usize,
+    pub name: Option<String>,
+    pub reason: String,
+}
+
+/// Servers of a list, with the entries that were skipped as malformed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ParsedServerList {
+    pub servers: Vec<GameServer>,
+    pub skipped: Vec<SkippedServer>,
+}
+
+/// Parse the XML server list returned by `get_server_list.php`.
+///
+/// Only a body that is not XML at all is an error; malformed `<server>`
+/// elements are skipped and reported in [`ParsedServerList::skipped`].
+pub fn parse_server_list(xml: &str) -> Result<ParsedServerList, String> {
+    let document =
+        Document::parse(xml).map_err(|e| format!("Failed to parse server list: {}", e))?;
+
+    let mut list = ParsedServerList::default();
+    let servers = document
+        .root_element()
+        .children()
+        .filter(|node| node.has_tag_name("server"));
+    for (index, node) in servers.enumerate() {
+        match parse_server(node) {
+            Ok(server) => list.servers.push(server),
+            Err(reason) => list.skipped.push(SkippedServer {
+                index,
+                name: child_text(node, "name").map(str::to_string),
+                reason,
+            }),
+        }
+    }
+    Ok(list)
+}
+
+fn parse_server(node: Node) -> Result<GameServer, String> {
+    let address = child_text(node, "address")
+        .filter(|address| !address.is_empty())
+        .ok_or("missing address")?;
+    let port = child_number(node, "port")?.ok_or("missing port")?;
+
+    Ok(GameServer {
+        name: text_or_empty(node, "name"),
+        address: address.to_string(),
+        port,
+        map_id: text_or_empty(node, "map_id"),
+        map_name: text_or_empty(node, "map_name"),
+        mode: text_or_empty(node, "mode"),
+        realm: text_or_empty(node, "realm"),
+        version: text_or_empty(node, "version"),
+        dedicated: match child_text(node, "dedicated") {
+            None | Some("" | "0" | "false") => false,
+            Some("1" | "true") => true,
+            Some(other) => return Err(format!("invalid dedicated flag {:?}", other)),
+        },
+        bots: child_number(node, "bots")?.unwrap_or(0),
+        current_players: child_number(node, "current_players")?.unwrap_or(0),
+        max_players: child_number(node, "max_players")?.unwrap_or(0),
+        comment: text_or_empty(node, "comment"),
+        url: text_or_empty(node, "url"),
+        players: node
+            .children()
+            .filter(|child| child.has_tag_name("player"))
+            .filter_map(|child| child.text())
+            .map(str::trim)
+            .filter(|player| !player.is_empty())
+            .map(str::to_string)
+            .collect(),
+    })
+}
+
+/// Trimmed text of the first `name` child, `None` when there is no such child.
+fn child_text<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.children()
+        .find(|child| child.has_tag_name(name))
+        .map(|child| child.text().unwrap_or("").trim())
+}
+
+fn text_or_empty(node: Node, name: &str) -> String {
+    child_text(node, name).unwrap_or("").to_string()
+}
+
+/// The `name` child as a number, `None` when it is missing or empty.
+fn child_number<T: FromStr>(node: Node, name: &str) -> Result<Option<T>, String> {
+    match child_text(node, name) {
+        None | Some("") => Ok(None),
+        Some(text) => text
+            .parse()
+            .map(Some)
+            .map_err(|_| format!("invalid {} {:?}", name, text)),
+    }
+}
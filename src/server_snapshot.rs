@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::Mutex as AsyncMutex;
+use tracing::{info, warn};
+
+use crate::{
+    ApiCache, GameServer, ProxyRoute, SkippedServer, canonical_query, etag_for, parse_server_list,
+};
+
+/// A snapshot whose pages are served stale is still kept this long, so
+/// requests do not merge the same pages again until they are refreshed.
+const MIN_SNAPSHOT_LIFETIME: Duration = Duration::from_secs(1);
+
+/// How the merged server list is fetched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServerSnapshotConfig {
+    /// Servers requested per upstream page.
+    pub page_size: usize,
+    /// Pages fetched at most, so an upstream that never returns a short page
+    /// cannot keep the merge running.
+    pub max_pages: usize,
+}
+
+/// Every server of the upstream list, merged from all its pages and
+/// de-duplicated by address and port.
+#[derive(Debug, Clone)]
+pub struct ServerSnapshot {
+    /// Hash of the merged servers, so it changes whenever they change.
+    pub id: String,
+    /// When the oldest page of the snapshot was fetched from upstream.
+    pub fetched_at: SystemTime,
+    pub pages: usize,
+    pub servers: Vec<GameServer>,
+    /// Malformed entries of all pages, indexed by position in the full list.
+    pub skipped: Vec<SkippedServer>,
+    expires_at: Instant,
+}
+
+impl ServerSnapshot {
+    /// Time until the first page of the snapshot expires, zero once it has.
+    pub fn remaining_ttl(&self) -> Duration {
+        self.expires_at.saturating_duration_since(Instant::now())
+    }
+}
+
+/// Merged server list shared by all requests. It is built from the cached
+/// pages of a route and rebuilt once its first page expires.
+pub struct ServerSnapshots {
+    cache: Arc<ApiCache>,
+    route: ProxyRoute,
+    config: ServerSnapshotConfig,
+    current: Mutex<Option<Arc<ServerSnapshot>>>,
+    // Held while a snapshot is built, so concurrent requests build it once
+    building: AsyncMutex<()>,
+}
+
+impl ServerSnapshots {
+    pub fn new(cache: Arc<ApiCache>, route: ProxyRoute, config: ServerSnapshotConfig) -> Self {
+        Self {
+            cache,
+            route,
+            config,
+            current: Mutex::new(None),
+            building: AsyncMutex::new(()),
+        }
+    }
+
+    /// The current snapshot, rebuilt when it has expired. While one request
+    /// rebuilds it, the others are served the previous snapshot rather than
+    /// waiting. The previous snapshot is returned when a rebuild fails, and
+    /// kept for the route's negative TTL so requests do not rebuild it
+    /// against a failing upstream.
+    pub async fn current(&self) -> Result<Arc<ServerSnapshot>, String> {
+        if let Some(snapshot) = self.unexpired() {
+            return Ok(snapshot);
+        }
+        let _building = match self.building.try_lock() {
+            Ok(building) => building,
+            Err(_) => {
+                if let Some(previous) = self.current.lock().unwrap().clone() {
+                    return Ok(previous);
+                }
+                // Nothing to serve yet, so wait for the first build
+                self.building.lock().await
+            }
+        };
+        // Another request may have rebuilt it while this one waited
+        if let Some(snapshot) = self.unexpired() {
+            return Ok(snapshot);
+        }
+
+        match self.build().await {
+            Ok(snapshot) => {
+                let snapshot = Arc::new(snapshot);
+                *self.current.lock().unwrap() = Some(snapshot.clone());
+                Ok(snapshot)
+            }
+            Err(e) => {
+                let mut current = self.current.lock().unwrap();
+                let Some(previous) = current.as_ref() else {
+                    return Err(e);
+                };
+                let retry_in = self.route.policy.negative_ttl.max(MIN_SNAPSHOT_LIFETIME);
+                warn!(
+                    "{}, serving previous snapshot {} for {:?}",
+                    e, previous.id, retry_in
+                );
+                let extended = Arc::new(ServerSnapshot {
+                    expires_at: Instant::now() + retry_in,
+                    ..ServerSnapshot::clone(previous)
+                });
+                *current = Some(extended.clone());
+                Ok(extended)
+            }
+        }
+    }
+
+    fn unexpired(&self) -> Option<Arc<ServerSnapshot>> {
+        self.current
+            .lock()
+            .unwrap()
+            .clone()
+            .filter(|snapshot| !snapshot.remaining_ttl().is_zero())
+    }
+
+    /// Fetch pages through the cache until a page is not full and merge them.
+    async fn build(&self) -> Result<ServerSnapshot, String> {
+        let page_size = self.config.page_size.max(1);
+        let max_pages = self.config.max_pages.max(1);
+        let mut servers: Vec<GameServer> = Vec::new();
+        // When the page each server was taken from was fetched
+        let mut fetched: Vec<SystemTime> = Vec::new();
+        let mut positions: HashMap<String, usize> = HashMap::new();
+        let mut skipped = Vec::new();
+        let mut fetched_at = SystemTime::now();
+        let mut expires_in = Duration::MAX;
+        let mut pages = 0;
+
+        loop {
+            if pages == max_pages {
+                warn!(
+                    "Server list has more than {} pages, ignoring the rest",
+                    max_pages
+                );
+                break;
+            }
+            let start = pages * page_size;
+            let query = canonical_query(
+                &format!("start={}&size={}&names=1", start, page_size),
+                self.route.params,
+            );
+            let lookup = self.cache.lookup_route(&self.route, &query).await?;
+            if !(200..300).contains(&lookup.status_code) {
+                return Err(format!(
+                    "Server list page at {} returned status {}",
+                    start, lookup.status_code
+                ));
+            }
+            let page = parse_server_list(&lookup.data)?;
+            pages += 1;
+            fetched_at = fetched_at.min(lookup.last_modified);
+            expires_in = expires_in.min(lookup.remaining_ttl());
+
+            let entries = page.servers.len() + page.skipped.len();
+            skipped.extend(page.skipped.into_iter().map(|entry| SkippedServer {
+                index: start + entry.index,
+                ..entry
+            }));
+            for server in page.servers {
+                match positions.get(&server.endpoint()) {
+                    // Servers move between pages as player counts change, so
+                    // keep the copy from the most recently fetched page
+                    Some(&index) => {
+                        if lookup.last_modified > fetched[index] {
+                            servers[index] = server;
+                            fetched[index] = lookup.last_modified;
+                        }
+                    }
+                    None => {
+                        positions.insert(server.endpoint(), servers.len());
+                        servers.push(server);
+                        fetched.push(lookup.last_modified);
+                    }
+                }
+            }
+            if entries < page_size {
+                break;
+            }
+        }
+
+        for entry in &skipped {
+            warn!(
+                "Skipped malformed server {} ({:?}): {}",
+                entry.index, entry.name, entry.reason
+            );
+        }
+
+        let id = etag_for(&serde_json::to_vec(&servers).unwrap_or_default())
+            .trim_matches('"')
+            .to_string();
+        info!(
+            "Built server snapshot {} with {} servers from {} pages",
+            id,
+            servers.len(),
+            pages
+        );
+        Ok(ServerSnapshot {
+            id,
+            fetched_at,
+            pages,
+            servers,
+            skipped,
+            expires_at: Instant::now() + expires_in.max(MIN_SNAPSHOT_LIFETIME),
+        })
+    }
+}
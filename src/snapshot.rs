@@ -0,0 +1,92 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Version of the snapshot file format, bumped on incompatible changes.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Cache contents written to disk so a restarted server starts warm.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheSnapshot {
+    pub version: u32,
+    /// Wall-clock time the snapshot was taken, in milliseconds since the Unix epoch.
+    pub saved_at_ms: u64,
+    /// Entries ordered from least to most recently used.
+    pub entries: Vec<SnapshotEntry>,
+}
+
+/// One successful response of the cache.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotEntry {
+    pub key: String,
+    pub data: String,
+    pub status_code: u16,
+    /// Wall-clock time the body was fetched, in milliseconds since the Unix epoch.
+    pub fetched_at_ms: u64,
+    /// How long the entry was fresh for. Restored without a TTL, the cache's
+    /// default applies.
+    #[serde(default)]
+    pub ttl_ms: Option<u64>,
+}
+
+impl CacheSnapshot {
+    pub fn new(entries: Vec<SnapshotEntry>) -> Self {
+        Self {
+            version: SNAPSHOT_VERSION,
+            saved_at_ms: to_unix_ms(SystemTime::now()),
+            entries,
+        }
+    }
+
+    /// Read a snapshot file. Returns `Ok(None)` when the file does not exist.
+    pub async fn read_from(path: &Path) -> Result<Option<Self>, String> {
+        let content = match tokio::fs::read_to_string(path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(format!(
+                    "Failed to read cache snapshot '{}': {}",
+                    path.display(),
+                    e
+                ));
+            }
+        };
+
+        let snapshot: CacheSnapshot = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse cache snapshot '{}': {}", path.display(), e))?;
+        if snapshot.version != SNAPSHOT_VERSION {
+            return Err(format!(
+                "Unsupported cache snapshot version {} in '{}'",
+                snapshot.version,
+                path.display()
+            ));
+        }
+        Ok(Some(snapshot))
+    }
+
+    /// Write the snapshot to `path`. The file is written next to its final
+    /// location and renamed, so a crash never leaves a truncated snapshot.
+    pub async fn write_to(&self, path: &Path) -> Result<(), String> {
+        let content = serde_json::to_vec(self)
+            .map_err(|e| format!("Failed to serialize cache snapshot: {}", e))?;
+
+        let mut tmp_path = path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+        tokio::fs::write(&tmp_path, content)
+            .await
+            .map_err(|e| format!("Failed to write cache snapshot '{}': {}", path.display(), e))?;
+        tokio::fs::rename(&tmp_path, path)
+            .await
+            .map_err(|e| format!("Failed to write cache snapshot '{}': {}", path.display(), e))
+    }
+}
+
+pub(crate) fn to_unix_ms(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+pub(crate) fn from_unix_ms(ms: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_millis(ms)
+}
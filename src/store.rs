@@ -0,0 +1,264 @@
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, RandomState};
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime};
+use tracing::{info, warn};
+
+use crate::CachedResponse;
+
+mod disk;
+mod redis;
+
+pub use disk::DiskStore;
+pub use redis::RedisStore;
+
+/// Entry count and approximate size of a store.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StoreStats {
+    pub entries: usize,
+    pub size_bytes: usize,
+}
+
+/// Storage behind an [`crate::ApiCache`]. The cache decides what is fresh or
+/// servable; a store only keeps entries until their retention has passed.
+#[async_trait]
+pub trait CacheStore: Send + Sync {
+    /// The entry stored under `key`. Stores that evict by use count the read
+    /// as a use.
+    async fn get(&self, key: &str) -> Result<Option<CachedResponse>, String>;
+
+    /// Store `entry` under `key`, replacing any previous entry. The store may
+    /// drop it once `retention` has passed since it was fetched.
+    async fn put(
+        &self,
+        key: &str,
+        entry: CachedResponse,
+        retention: Duration,
+    ) -> Result<(), String>;
+
+    /// Remove the entry under `key`. Returns whether there was one.
+    async fn remove(&self, key: &str) -> Result<bool, String>;
+
+    /// Every stored entry, least recently used first when the store tracks use.
+    async fn entries(&self) -> Result<Vec<(String, CachedResponse)>, String>;
+
+    /// Drop entries whose retention has passed. Returns the number removed.
+    async fn sweep_expired(&self) -> Result<usize, String>;
+
+    /// Entry count and size. Served by the public `/api/status`, so it must
+    /// not read the entries themselves.
+    async fn stats(&self) -> Result<StoreStats, String>;
+}
+
+/// Time at which an entry fetched at `fetched_at` may be dropped.
+fn expiry(entry: &CachedResponse, retention: Duration) -> SystemTime {
+    entry.fetched_at + retention
+}
+
+struct MemoryEntry {
+    response: CachedResponse,
+    expires_at: SystemTime,
+    // Value of the store's access clock at the last read, used for LRU eviction
+    last_access: AtomicU64,
+}
+
+/// Number of independently locked parts of a [`MemoryStore`].
+const SHARDS: usize = 16;
+
+type Shard = RwLock<HashMap<String, MemoryEntry>>;
+
+/// In-process store, bounded by entry count and size with LRU eviction.
+/// This is the default store. Entries are spread over several locks so
+/// concurrent requests for different keys rarely wait on each other.
+pub struct MemoryStore {
+    shards: Box<[Shard]>,
+    hasher: RandomState,
+    entry_count: AtomicUsize,
+    // Running size of keys and bodies, used for the size budget
+    total_bytes: AtomicUsize,
+    // Monotonic counter handed out on every access to order entries for LRU eviction
+    access_clock: AtomicU64,
+    max_entries: usize,
+    max_bytes: usize,
+}
+
+impl Default for MemoryStore {
+    fn default() -> Self {
+        Self::new(0, 0)
+    }
+}
+
+impl MemoryStore {
+    /// Store bounded to `max_entries` entries and `max_bytes` bytes of keys
+    /// and bodies. A limit of 0 disables that limit.
+    pub fn new(max_entries: usize, max_bytes: usize) -> Self {
+        Self {
+            shards: (0..SHARDS).map(|_| Shard::default()).collect(),
+            hasher: RandomState::new(),
+            entry_count: AtomicUsize::new(0),
+            total_bytes: AtomicUsize::new(0),
+            access_clock: AtomicU64::new(0),
+            max_entries,
+            max_bytes,
+        }
+    }
+
+    fn next_access_tick(&self) -> u64 {
+        self.access_clock.fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn shard(&self, key: &str) -> &Shard {
+        &self.shards[self.hasher.hash_one(key) as usize % SHARDS]
+    }
+
+    fn insert(&self, key: &str, entry: MemoryEntry) {
+        // Count the new entry before it becomes visible, so the totals never
+        // drop below what the map holds
+        self.total_bytes
+            .fetch_add(entry.response.size_bytes(key), Ordering::Relaxed);
+        let old = self
+            .shard(key)
+            .write()
+            .unwrap()
+            .insert(key.to_string(), entry);
+        match old {
+            Some(old) => {
+                self.total_bytes
+                    .fetch_sub(old.response.size_bytes(key), Ordering::Relaxed);
+            }
+            None => {
+                self.entry_count.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn remove_entry(&self, key: &str) -> Option<MemoryEntry> {
+        let entry = self.shard(key).write().unwrap().remove(key)?;
+        self.forget(key, &entry);
+        Some(entry)
+    }
+
+    /// Update the totals for an entry that was taken out of its shard.
+    fn forget(&self, key: &str, entry: &MemoryEntry) {
+        self.entry_count.fetch_sub(1, Ordering::Relaxed);
+        self.total_bytes
+            .fetch_sub(entry.response.size_bytes(key), Ordering::Relaxed);
+    }
+
+    fn over_limits(&self) -> bool {
+        (self.max_entries > 0 && self.entry_count.load(Ordering::Relaxed) > self.max_entries)
+            || (self.max_bytes > 0 && self.total_bytes.load(Ordering::Relaxed) > self.max_bytes)
+    }
+
+    /// Remove the least recently used entry other than `keep`.
+    fn evict_lru(&self, keep: &str) -> Option<String> {
+        let key = self
+            .shards
+            .iter()
+            .filter_map(|shard| {
+                let entries = shard.read().unwrap();
+                entries
+                    .iter()
+                    .filter(|(key, _)| key.as_str() != keep)
+                    .map(|(key, entry)| (entry.last_access.load(Ordering::Relaxed), key.clone()))
+                    .min()
+            })
+            .min()
+            .map(|(_, key)| key)?;
+        self.remove_entry(&key);
+        Some(key)
+    }
+}
+
+#[async_trait]
+impl CacheStore for MemoryStore {
+    async fn get(&self, key: &str) -> Result<Option<CachedResponse>, String> {
+        let entries = self.shard(key).read().unwrap();
+        Ok(entries.get(key).map(|entry| {
+            entry
+                .last_access
+                .store(self.next_access_tick(), Ordering::Relaxed);
+            entry.response.clone()
+        }))
+    }
+
+    async fn put(
+        &self,
+        key: &str,
+        entry: CachedResponse,
+        retention: Duration,
+    ) -> Result<(), String> {
+        if self.max_bytes > 0 && entry.size_bytes(key) > self.max_bytes {
+            warn!(
+                "Response for {} is larger than the cache budget of {} bytes, not caching",
+                key, self.max_bytes
+            );
+            return Ok(());
+        }
+
+        let expires_at = expiry(&entry, retention);
+        self.insert(
+            key,
+            MemoryEntry {
+                response: entry,
+                expires_at,
+                last_access: AtomicU64::new(self.next_access_tick()),
+            },
+        );
+        while self.over_limits() {
+            match self.evict_lru(key) {
+                Some(evicted) => info!("Evicted least recently used cache entry {}", evicted),
+                None => break,
+            }
+        }
+        Ok(())
+    }
+
+    async fn remove(&self, key: &str) -> Result<bool, String> {
+        Ok(self.remove_entry(key).is_some())
+    }
+
+    async fn entries(&self) -> Result<Vec<(String, CachedResponse)>, String> {
+        let mut all: Vec<(u64, String, CachedResponse)> = Vec::new();
+        for shard in self.shards.iter() {
+            let entries = shard.read().unwrap();
+            all.extend(entries.iter().map(|(key, entry)| {
+                (
+                    entry.last_access.load(Ordering::Relaxed),
+                    key.clone(),
+                    entry.response.clone(),
+                )
+            }));
+        }
+        all.sort_by_key(|(last_access, _, _)| *last_access);
+        Ok(all
+            .into_iter()
+            .map(|(_, key, response)| (key, response))
+            .collect())
+    }
+
+    async fn sweep_expired(&self) -> Result<usize, String> {
+        let now = SystemTime::now();
+        let mut removed = 0;
+        for shard in self.shards.iter() {
+            shard.write().unwrap().retain(|key, entry| {
+                if entry.expires_at >= now {
+                    return true;
+                }
+                self.forget(key, entry);
+                removed += 1;
+                false
+            });
+        }
+        Ok(removed)
+    }
+
+    async fn stats(&self) -> Result<StoreStats, String> {
+        Ok(StoreStats {
+            entries: self.entry_count.load(Ordering::Relaxed),
+            size_bytes: self.total_bytes.load(Ordering::Relaxed),
+        })
+    }
+}
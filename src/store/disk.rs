@@ -0,0 +1,254 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tracing::warn;
+
+use super::{CacheStore, StoreStats, expiry};
+use crate::CachedResponse;
+
+/// Store keeping one JSON file per entry in a directory. The cache survives
+/// restarts and can be shared by replicas that mount the same directory.
+/// A file's modification time is set to its entry's expiry, so stats are
+/// taken from metadata alone.
+pub struct DiskStore {
+    dir: PathBuf,
+}
+
+/// Contents of an entry file. The key is kept so entries can be listed and
+/// a file is only ever read back for its own key.
+#[derive(Serialize, Deserialize)]
+struct DiskRecord {
+    key: String,
+    expires_at: SystemTime,
+    entry: CachedResponse,
+}
+
+impl DiskStore {
+    /// Store entries in `dir`, which is created on the first write.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// File of `key`, named after the SHA-256 of the key. The hash is stable
+    /// across builds, so every replica maps a key to the same file, and wide
+    /// enough that two keys never share a file.
+    fn path_for(&self, key: &str) -> PathBuf {
+        let hex: String = Sha256::digest(key.as_bytes())
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        self.dir.join(format!("{}.json", hex))
+    }
+
+    async fn read_record(path: &Path) -> Result<Option<DiskRecord>, String> {
+        let content = match tokio::fs::read(path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(format!(
+                    "Failed to read cache file '{}': {}",
+                    path.display(),
+                    e
+                ));
+            }
+        };
+        serde_json::from_slice(&content)
+            .map(Some)
+            .map_err(|e| format!("Failed to parse cache file '{}': {}", path.display(), e))
+    }
+
+    /// Paths of every entry file in the directory.
+    async fn entry_paths(&self) -> Result<Vec<PathBuf>, String> {
+        let mut dir = match tokio::fs::read_dir(&self.dir).await {
+            Ok(dir) => dir,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(format!(
+                    "Failed to read cache directory '{}': {}",
+                    self.dir.display(),
+                    e
+                ));
+            }
+        };
+
+        let mut paths = Vec::new();
+        loop {
+            match dir.next_entry().await {
+                Ok(Some(entry)) => {
+                    let path = entry.path();
+                    if path.extension().is_some_and(|ext| ext == "json") {
+                        paths.push(path);
+                    }
+                }
+                Ok(None) => return Ok(paths),
+                Err(e) => {
+                    return Err(format!(
+                        "Failed to read cache directory '{}': {}",
+                        self.dir.display(),
+                        e
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Every readable record, logging and skipping files that cannot be parsed.
+    async fn records(&self) -> Result<Vec<(PathBuf, DiskRecord)>, String> {
+        let mut records = Vec::new();
+        for path in self.entry_paths().await? {
+            match Self::read_record(&path).await {
+                Ok(Some(record)) => records.push((path, record)),
+                Ok(None) => {}
+                Err(e) => warn!("{}", e),
+            }
+        }
+        Ok(records)
+    }
+}
+
+/// Write `content` to `path` with `expires_at` as its modification time.
+async fn write_file(path: &Path, content: Vec<u8>, expires_at: SystemTime) -> std::io::Result<()> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let mut file = std::fs::File::create(&path)?;
+        file.write_all(&content)?;
+        file.set_modified(expires_at)
+    })
+    .await
+    .map_err(std::io::Error::other)?
+}
+
+async fn remove_file(path: &Path) -> Result<bool, String> {
+    match tokio::fs::remove_file(path).await {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(format!(
+            "Failed to remove cache file '{}': {}",
+            path.display(),
+            e
+        )),
+    }
+}
+
+#[async_trait]
+impl CacheStore for DiskStore {
+    async fn get(&self, key: &str) -> Result<Option<CachedResponse>, String> {
+        let path = self.path_for(key);
+        match Self::read_record(&path).await? {
+            Some(record) if record.key == key => {
+                if record.expires_at < SystemTime::now() {
+                    remove_file(&path).await?;
+                    return Ok(None);
+                }
+                Ok(Some(record.entry))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    async fn put(
+        &self,
+        key: &str,
+        entry: CachedResponse,
+        retention: Duration,
+    ) -> Result<(), String> {
+        tokio::fs::create_dir_all(&self.dir).await.map_err(|e| {
+            format!(
+                "Failed to create cache directory '{}': {}",
+                self.dir.display(),
+                e
+            )
+        })?;
+
+        let expires_at = expiry(&entry, retention);
+        let record = DiskRecord {
+            key: key.to_string(),
+            expires_at,
+            entry,
+        };
+        let content = serde_json::to_vec(&record)
+            .map_err(|e| format!("Failed to serialize cached entry {}: {}", key, e))?;
+
+        // Write to a unique temporary file and rename it, so readers never see
+        // a partial entry even when replicas write the same key concurrently
+        let path = self.path_for(key);
+        let tmp_path = path.with_extension(format!("{:016x}.tmp", fastrand::u64(..)));
+        let result = async {
+            write_file(&tmp_path, content, expires_at).await?;
+            tokio::fs::rename(&tmp_path, &path).await
+        }
+        .await;
+        if let Err(e) = result {
+            let _ = tokio::fs::remove_file(&tmp_path).await;
+            return Err(format!(
+                "Failed to write cache file '{}': {}",
+                path.display(),
+                e
+            ));
+        }
+        Ok(())
+    }
+
+    async fn remove(&self, key: &str) -> Result<bool, String> {
+        let path = self.path_for(key);
+        match Self::read_record(&path).await {
+            Ok(Some(record)) if record.key != key => Ok(false),
+            Ok(None) => Ok(false),
+            _ => remove_file(&path).await,
+        }
+    }
+
+    async fn entries(&self) -> Result<Vec<(String, CachedResponse)>, String> {
+        let now = SystemTime::now();
+        let mut entries: Vec<(String, CachedResponse)> = self
+            .records()
+            .await?
+            .into_iter()
+            .filter(|(_, record)| record.expires_at >= now)
+            .map(|(_, record)| (record.key, record.entry))
+            .collect();
+        // Use is not tracked on disk, so the oldest entries come first
+        entries.sort_by_key(|(_, entry)| entry.fetched_at);
+        Ok(entries)
+    }
+
+    async fn sweep_expired(&self) -> Result<usize, String> {
+        let now = SystemTime::now();
+        let mut removed = 0;
+        for (path, record) in self.records().await? {
+            if record.expires_at < now && remove_file(&path).await? {
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    async fn stats(&self) -> Result<StoreStats, String> {
+        let now = SystemTime::now();
+        let mut stats = StoreStats::default();
+        for path in self.entry_paths().await? {
+            let metadata = match tokio::fs::metadata(&path).await {
+                Ok(metadata) => metadata,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => {
+                    return Err(format!(
+                        "Failed to read cache file '{}': {}",
+                        path.display(),
+                        e
+                    ));
+                }
+            };
+            if metadata
+                .modified()
+                .is_ok_and(|expires_at| expires_at >= now)
+            {
+                stats.entries += 1;
+                stats.size_bytes += metadata.len() as usize;
+            }
+        }
+        Ok(stats)
+    }
+}
@@ -0,0 +1,211 @@
+use async_trait::async_trait;
+use redis::AsyncCommands;
+use redis::aio::{ConnectionManager, ConnectionManagerConfig};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+
+use super::{CacheStore, StoreStats};
+use crate::CachedResponse;
+
+/// Limit on connecting and on each command, so an unhealthy Redis delays
+/// requests only briefly before they fall through to the upstream.
+const REDIS_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How long Redis is skipped after a failed connect, so requests go straight
+/// to the upstream instead of each waiting for another connect to time out.
+const REDIS_RECONNECT_BACKOFF: Duration = Duration::from_secs(10);
+
+/// Store in a Redis-compatible server, so several replicas share one cache.
+/// Entries are JSON values under `prefix` + key and expire through Redis
+/// itself, so eviction follows the server's `maxmemory-policy`.
+pub struct RedisStore {
+    client: redis::Client,
+    // Connected on first use so the server can start while Redis is down
+    connection: OnceCell<ConnectionManager>,
+    // When the last connect failed
+    connect_failed_at: Mutex<Option<Instant>>,
+    prefix: String,
+}
+
+impl RedisStore {
+    pub fn new(url: &str, prefix: &str) -> Result<Self, String> {
+        let client =
+            redis::Client::open(url).map_err(|e| format!("Invalid Redis URL '{}': {}", url, e))?;
+        Ok(Self {
+            client,
+            connection: OnceCell::new(),
+            connect_failed_at: Mutex::new(None),
+            prefix: prefix.to_string(),
+        })
+    }
+
+    async fn connection(&self) -> Result<ConnectionManager, String> {
+        self.connection
+            .get_or_try_init(|| async {
+                // Checked here as well, as requests queued behind a failed
+                // connect would otherwise each try again
+                if let Some(failed_at) = *self.connect_failed_at.lock().unwrap()
+                    && failed_at.elapsed() < REDIS_RECONNECT_BACKOFF
+                {
+                    return Err("Redis is unavailable, not reconnecting yet".to_string());
+                }
+                let config = ConnectionManagerConfig::new()
+                    .set_number_of_retries(0)
+                    .set_connection_timeout(REDIS_TIMEOUT)
+                    .set_response_timeout(REDIS_TIMEOUT);
+                ConnectionManager::new_with_config(self.client.clone(), config)
+                    .await
+                    .map_err(|e| {
+                        *self.connect_failed_at.lock().unwrap() = Some(Instant::now());
+                        format!("Failed to connect to Redis: {}", e)
+                    })
+            })
+            .await
+            .cloned()
+    }
+
+    fn redis_key(&self, key: &str) -> String {
+        format!("{}{}", self.prefix, key)
+    }
+
+    /// Every key under this store's prefix.
+    async fn keys(&self, connection: &mut ConnectionManager) -> Result<Vec<String>, String> {
+        let mut keys = Vec::new();
+        let mut cursor: u64 = 0;
+        loop {
+            let (next, batch): (u64, Vec<String>) = redis::cmd("SCAN")
+                .arg(cursor)
+                .arg("MATCH")
+                .arg(format!("{}*", escape_pattern(&self.prefix)))
+                .arg("COUNT")
+                .arg(1000)
+                .query_async(connection)
+                .await
+                .map_err(redis_error)?;
+            keys.extend(batch);
+            if next == 0 {
+                return Ok(keys);
+            }
+            cursor = next;
+        }
+    }
+}
+
+/// Escape glob characters so the prefix is matched literally by SCAN.
+fn escape_pattern(prefix: &str) -> String {
+    let mut escaped = String::with_capacity(prefix.len());
+    for c in prefix.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn redis_error(e: redis::RedisError) -> String {
+    format!("Redis request failed: {}", e)
+}
+
+fn decode(key: &str, value: &str) -> Result<CachedResponse, String> {
+    serde_json::from_str(value)
+        .map_err(|e| format!("Failed to parse cached entry {} from Redis: {}", key, e))
+}
+
+#[async_trait]
+impl CacheStore for RedisStore {
+    async fn get(&self, key: &str) -> Result<Option<CachedResponse>, String> {
+        let mut connection = self.connection().await?;
+        let value: Option<String> = connection
+            .get(self.redis_key(key))
+            .await
+            .map_err(redis_error)?;
+        value.map(|value| decode(key, &value)).transpose()
+    }
+
+    async fn put(
+        &self,
+        key: &str,
+        entry: CachedResponse,
+        retention: Duration,
+    ) -> Result<(), String> {
+        let remaining = retention.saturating_sub(entry.age());
+        let mut connection = self.connection().await?;
+        if remaining.is_zero() {
+            let _: () = connection
+                .del(self.redis_key(key))
+                .await
+                .map_err(redis_error)?;
+            return Ok(());
+        }
+
+        let value = serde_json::to_string(&entry)
+            .map_err(|e| format!("Failed to serialize cached entry {}: {}", key, e))?;
+        let millis = u64::try_from(remaining.as_millis())
+            .unwrap_or(u64::MAX)
+            .max(1);
+        redis::cmd("SET")
+            .arg(self.redis_key(key))
+            .arg(value)
+            .arg("PX")
+            .arg(millis)
+            .query_async(&mut connection)
+            .await
+            .map_err(redis_error)
+    }
+
+    async fn remove(&self, key: &str) -> Result<bool, String> {
+        let mut connection = self.connection().await?;
+        let removed: u64 = connection
+            .del(self.redis_key(key))
+            .await
+            .map_err(redis_error)?;
+        Ok(removed > 0)
+    }
+
+    async fn entries(&self) -> Result<Vec<(String, CachedResponse)>, String> {
+        let mut connection = self.connection().await?;
+        let keys = self.keys(&mut connection).await?;
+        let mut entries = Vec::with_capacity(keys.len());
+        for redis_key in keys {
+            let value: Option<String> = connection.get(&redis_key).await.map_err(redis_error)?;
+            let key = redis_key[self.prefix.len()..].to_string();
+            // Skip keys that expired since the scan and values that are not ours
+            if let Some(value) = value
+                && let Ok(entry) = decode(&key, &value)
+            {
+                entries.push((key, entry));
+            }
+        }
+        Ok(entries)
+    }
+
+    async fn sweep_expired(&self) -> Result<usize, String> {
+        // Redis expires keys on its own
+        Ok(0)
+    }
+
+    async fn stats(&self) -> Result<StoreStats, String> {
+        let mut connection = self.connection().await?;
+        let keys = self.keys(&mut connection).await?;
+        // One round trip for every length rather than one per key
+        let mut pipe = redis::pipe();
+        for key in &keys {
+            pipe.strlen(key);
+        }
+        let lengths: Vec<usize> = if keys.is_empty() {
+            Vec::new()
+        } else {
+            pipe.query_async(&mut connection)
+                .await
+                .map_err(redis_error)?
+        };
+        let size_bytes =
+            keys.iter().map(String::len).sum::<usize>() + lengths.iter().sum::<usize>();
+        Ok(StoreStats {
+            entries: keys.len(),
+            size_bytes,
+        })
+    }
+}
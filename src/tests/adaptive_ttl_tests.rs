@@ -0,0 +1,185 @@
+#[cfg(test)]
+mod tests {
+    use crate::adaptive_ttl::ChangeTracker;
+    use crate::tests::helpers::test_route;
+    use crate::{AdaptiveTtl, ApiCache, CachePolicy};
+    use std::time::Duration;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{method, path},
+    };
+
+    fn bounds() -> AdaptiveTtl {
+        AdaptiveTtl::new(2, 60)
+    }
+
+    #[test]
+    fn test_initial_ttl_is_clamped_to_bounds() {
+        let tracker = ChangeTracker::new("body", 200, Duration::from_secs(1), bounds());
+        assert_eq!(tracker.ttl(), Duration::from_secs(2));
+
+        let tracker = ChangeTracker::new("body", 200, Duration::from_secs(600), bounds());
+        assert_eq!(tracker.ttl(), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_ttl_grows_when_body_never_changes() {
+        let mut tracker = ChangeTracker::new("body", 200, Duration::from_secs(10), bounds());
+
+        let mut previous = tracker.ttl();
+        for _ in 0..20 {
+            let ttl = tracker.observe("body", 200, bounds());
+            assert!(ttl >= previous);
+            previous = ttl;
+        }
+        assert!(previous > Duration::from_secs(55));
+        assert!(previous <= Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_ttl_shrinks_when_body_always_changes() {
+        let mut tracker = ChangeTracker::new("body 0", 200, Duration::from_secs(30), bounds());
+
+        for i in 1..=20 {
+            tracker.observe(&format!("body {}", i), 200, bounds());
+        }
+        assert!(tracker.ttl() < Duration::from_secs(3));
+        assert!(tracker.ttl() >= Duration::from_secs(2));
+
+        let status = tracker.status("key");
+        assert_eq!(status.refreshes, 20);
+        assert_eq!(status.changes, 20);
+        assert!(status.change_rate > 0.9);
+    }
+
+    #[test]
+    fn test_status_code_change_counts_as_change() {
+        let mut tracker = ChangeTracker::new("body", 200, Duration::from_secs(30), bounds());
+        tracker.observe("body", 203, bounds());
+        assert_eq!(tracker.status("key").changes, 1);
+    }
+
+    #[tokio::test]
+    async fn test_cache_adapts_ttl_per_key() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/get_server_list.php"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("same page"))
+            .mount(&mock_server)
+            .await;
+
+        let cache = ApiCache::new(10);
+        let route = test_route(
+            &mock_server,
+            CachePolicy::new(10, 1).with_adaptive_ttl(bounds()),
+        );
+        let key = route.cache_key("start=0");
+
+        cache.refresh_route(&route, "start=0").await.unwrap();
+        assert_eq!(cache.effective_ttl(&key), Some(Duration::from_secs(10)));
+
+        cache.refresh_route(&route, "start=0").await.unwrap();
+        let ttl = cache.effective_ttl(&key).unwrap();
+        assert!(ttl > Duration::from_secs(10));
+
+        let status = cache.adaptive_ttl_status();
+        assert_eq!(status.len(), 1);
+        assert_eq!(status[0].key, key);
+        assert_eq!(status[0].refreshes, 1);
+        assert_eq!(status[0].changes, 0);
+    }
+
+    #[tokio::test]
+    async fn test_fixed_policy_is_not_tracked() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/get_server_list.php"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("page"))
+            .mount(&mock_server)
+            .await;
+
+        let cache = ApiCache::new(10);
+        let route = test_route(&mock_server, CachePolicy::new(10, 1));
+        cache.refresh_route(&route, "start=0").await.unwrap();
+
+        assert!(cache.effective_ttl(&route.cache_key("start=0")).is_none());
+        assert!(cache.adaptive_ttl_status().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_errors_do_not_change_adaptive_ttl() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/get_server_list.php"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("page"))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/get_server_list.php"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&mock_server)
+            .await;
+
+        let cache = ApiCache::new(10);
+        let route = test_route(
+            &mock_server,
+            CachePolicy::new(10, 1).with_adaptive_ttl(bounds()),
+        );
+        cache.refresh_route(&route, "start=0").await.unwrap();
+        cache.refresh_route(&route, "start=0").await.unwrap();
+
+        let status = cache.adaptive_ttl_status();
+        assert_eq!(status[0].refreshes, 0);
+        assert_eq!(status[0].ttl_secs, 10.0);
+    }
+
+    #[tokio::test]
+    async fn test_history_dropped_with_evicted_entry() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/get_server_list.php"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("page"))
+            .mount(&mock_server)
+            .await;
+
+        let cache = ApiCache::new(10).with_limits(1, 0);
+        let route = test_route(
+            &mock_server,
+            CachePolicy::new(10, 1).with_adaptive_ttl(bounds()),
+        );
+        cache.lookup_route(&route, "start=0").await.unwrap();
+        cache.refresh_route(&route, "start=0").await.unwrap();
+        assert_eq!(cache.adaptive_ttl_status()[0].refreshes, 1);
+
+        // Evicts start=0, whose history starts over when it is fetched again
+        cache.lookup_route(&route, "start=100").await.unwrap();
+        cache.lookup_route(&route, "start=0").await.unwrap();
+        let status = cache.adaptive_ttl_status();
+        assert_eq!(status.len(), 2);
+        assert!(status.iter().all(|status| status.refreshes == 0));
+    }
+
+    #[tokio::test]
+    async fn test_idle_history_pruned_without_sweeper() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/get_server_list.php"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("page"))
+            .mount(&mock_server)
+            .await;
+
+        let cache = ApiCache::new(1);
+        let route = test_route(
+            &mock_server,
+            CachePolicy::new(1, 1).with_adaptive_ttl(AdaptiveTtl::new(1, 60)),
+        );
+        cache.refresh_route(&route, "start=0").await.unwrap();
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+
+        cache.refresh_route(&route, "start=100").await.unwrap();
+        let status = cache.adaptive_ttl_status();
+        assert_eq!(status.len(), 1);
+        assert_eq!(status[0].key, route.cache_key("start=100"));
+    }
+}
@@ -1,6 +1,8 @@
 #[cfg(test)]
 mod tests {
-    use crate::{Config, MapEntry, MapsConfig, RepoVersion, VersionInfo};
+    use crate::{
+        CacheBackend, CachePolicy, Config, MapEntry, MapsConfig, RepoVersion, VersionInfo,
+    };
 
     #[tokio::test]
     async fn test_config_default_values() {
@@ -9,7 +11,48 @@ mod tests {
             std::env::remove_var("HOST");
             std::env::remove_var("PORT");
             std::env::remove_var("CACHE_DURATION_SECS");
+            std::env::remove_var("CACHE_STALE_SECS");
+            std::env::remove_var("CACHE_STALE_IF_ERROR_SECS");
+            std::env::remove_var("CACHE_MAX_ENTRIES");
+            std::env::remove_var("CACHE_MAX_BYTES");
+            std::env::remove_var("CACHE_SWEEP_INTERVAL_SECS");
+            std::env::remove_var("CACHE_COMPRESSION");
+            std::env::remove_var("CACHE_BACKEND");
+            std::env::remove_var("CACHE_REDIS_URL");
+            std::env::remove_var("CACHE_REDIS_PREFIX");
+            std::env::remove_var("CACHE_DISK_PATH");
+            std::env::remove_var("CACHE_SNAPSHOT_PATH");
+            std::env::remove_var("CACHE_SNAPSHOT_INTERVAL_SECS");
+            std::env::remove_var("SERVER_LIST_CACHE_SECS");
+            std::env::remove_var("PLAYER_LIST_CACHE_SECS");
+            std::env::remove_var("VERSION_CACHE_SECS");
+            std::env::remove_var("SERVER_LIST_NEGATIVE_CACHE_SECS");
+            std::env::remove_var("PLAYER_LIST_NEGATIVE_CACHE_SECS");
+            std::env::remove_var("VERSION_NEGATIVE_CACHE_SECS");
+            std::env::remove_var("ADAPTIVE_TTL_MIN_SECS");
+            std::env::remove_var("ADAPTIVE_TTL_MAX_SECS");
+            std::env::remove_var("SERVER_LIST_UPSTREAMS");
+            std::env::remove_var("PLAYER_LIST_UPSTREAMS");
+            std::env::remove_var("SERVER_LIST_PAGE_SIZE");
+            std::env::remove_var("SERVER_LIST_MAX_PAGES");
+            std::env::remove_var("POLLER_INTERVAL_SECS");
+            std::env::remove_var("POLLER_QUERIES");
+            std::env::remove_var("POLLER_LEARNED_QUERIES");
+            std::env::remove_var("UPSTREAM_CONNECT_TIMEOUT_SECS");
+            std::env::remove_var("UPSTREAM_READ_TIMEOUT_SECS");
+            std::env::remove_var("UPSTREAM_TIMEOUT_SECS");
+            std::env::remove_var("UPSTREAM_USER_AGENT");
+            std::env::remove_var("UPSTREAM_MAX_RETRIES");
+            std::env::remove_var("UPSTREAM_RETRY_BACKOFF_MS");
+            std::env::remove_var("UPSTREAM_MAX_RESPONSE_BYTES");
+            std::env::remove_var("UPSTREAM_RATE_LIMIT");
+            std::env::remove_var("UPSTREAM_RATE_LIMIT_BURST");
+            std::env::remove_var("UPSTREAM_MAX_CONCURRENT");
+            std::env::remove_var("UPSTREAM_RATE_LIMIT_WAIT_MS");
+            std::env::remove_var("BREAKER_FAILURE_THRESHOLD");
+            std::env::remove_var("BREAKER_COOL_DOWN_SECS");
             std::env::remove_var("MAPS_CONFIG");
+            std::env::remove_var("ADMIN_TOKEN");
             std::env::remove_var("ANDROID_REPO_URL");
             std::env::remove_var("WEB_REPO_URL");
         }
@@ -20,9 +63,61 @@ mod tests {
         assert_eq!(config.host, "127.0.0.1");
         assert_eq!(config.port, "5800");
         assert_eq!(config.cache_duration_secs, 3);
+        assert_eq!(config.cache_stale_secs, 0);
+        assert_eq!(config.cache_stale_if_error_secs, 300);
+        assert_eq!(config.cache_max_entries, 1000);
+        assert_eq!(config.cache_max_bytes, 64 * 1024 * 1024);
+        assert_eq!(config.cache_sweep_interval_secs, 60);
+        assert!(config.cache_compression);
+        assert_eq!(config.cache_backend, CacheBackend::Memory);
+        assert_eq!(config.cache_redis_url, "redis://127.0.0.1:6379");
+        assert_eq!(config.cache_redis_prefix, "rwrs:");
+        assert_eq!(config.cache_disk_path, "cache");
+        assert!(config.cache_snapshot_path.is_none());
+        assert_eq!(config.cache_snapshot_interval_secs, 300);
+        assert_eq!(config.server_list_cache_secs, 3);
+        assert_eq!(config.player_list_cache_secs, 3);
+        assert_eq!(config.version_cache_secs, 300);
+        assert_eq!(config.server_list_negative_cache_secs, 1);
+        assert_eq!(config.player_list_negative_cache_secs, 1);
+        assert_eq!(config.version_negative_cache_secs, 60);
+        assert_eq!(config.adaptive_ttl_min_secs, 1);
+        assert_eq!(config.adaptive_ttl_max_secs, 0);
+        assert!(config.adaptive_ttl().is_none());
+        assert_eq!(config.server_list_policy(), CachePolicy::new(3, 1));
+        assert_eq!(config.player_list_policy(), CachePolicy::new(3, 1));
+        assert_eq!(config.version_policy(), CachePolicy::new(300, 60));
+        assert_eq!(
+            config.server_list_upstreams,
+            vec!["http://rwr.runningwithrifles.com/rwr_server_list/get_server_list.php"]
+        );
+        assert_eq!(
+            config.player_list_upstreams,
+            vec!["http://rwr.runningwithrifles.com/rwr_stats/view_players.php"]
+        );
+        assert_eq!(config.server_list_page_size, 100);
+        assert_eq!(config.server_list_max_pages, 20);
+        assert_eq!(config.poller_interval_secs, 0);
+        assert!(config.poller_queries.is_empty());
+        assert_eq!(config.poller_learned_queries, 5);
+        assert!(config.poller_config().is_none());
+        assert_eq!(config.upstream_connect_timeout_secs, 5);
+        assert_eq!(config.upstream_read_timeout_secs, 10);
+        assert_eq!(config.upstream_timeout_secs, 10);
+        assert!(config.upstream_user_agent.starts_with("rwrs-server/"));
+        assert_eq!(config.upstream_max_retries, 2);
+        assert_eq!(config.upstream_retry_backoff_ms, 200);
+        assert_eq!(config.upstream_max_response_bytes, 8 * 1024 * 1024);
+        assert_eq!(config.upstream_rate_limit, 10.0);
+        assert_eq!(config.upstream_rate_limit_burst, 20);
+        assert_eq!(config.upstream_max_concurrent, 8);
+        assert_eq!(config.upstream_rate_limit_wait_ms, 1000);
+        assert_eq!(config.breaker_failure_threshold, 5);
+        assert_eq!(config.breaker_cool_down_secs, 30);
         assert_eq!(config.maps_config_path, "maps.json");
         assert!(config.android_repo_url.is_none());
         assert!(config.web_repo_url.is_none());
+        assert!(config.admin_token.is_none());
     }
 
     #[tokio::test]
@@ -0,0 +1,50 @@
+#[cfg(test)]
+mod tests {
+    use crate::{ApiCache, CacheStatus, SharedBody};
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{method, path},
+    };
+
+    #[test]
+    fn test_clones_share_the_buffer() {
+        let body = SharedBody::from("<result></result>".to_string());
+        let clone = body.clone();
+        assert_eq!(body.as_ptr(), clone.as_ptr());
+
+        let bytes = body.to_bytes();
+        assert_eq!(bytes.as_ptr(), body.as_ptr());
+        assert_eq!(&bytes[..], b"<result></result>");
+    }
+
+    #[test]
+    fn test_serializes_as_string() {
+        let body = SharedBody::from("page 0");
+        let json = serde_json::to_string(&body).unwrap();
+        assert_eq!(json, "\"page 0\"");
+        let restored: SharedBody = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, "page 0");
+    }
+
+    #[tokio::test]
+    async fn test_cache_hits_share_the_cached_body() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/list"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("list_data"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        let url = format!("{}/list", mock_server.uri());
+
+        let cache = ApiCache::new(10);
+        let miss = cache.lookup(&url).await.unwrap();
+        let first = cache.lookup(&url).await.unwrap();
+        let second = cache.lookup(&url).await.unwrap();
+
+        assert_eq!(miss.status, CacheStatus::Miss);
+        assert_eq!(first.status, CacheStatus::Hit);
+        assert_eq!(first.data.as_ptr(), second.data.as_ptr());
+        assert_eq!(miss.data.as_ptr(), first.data.as_ptr());
+    }
+}
@@ -1,6 +1,14 @@
 #[cfg(test)]
 mod tests {
-    use crate::ApiCache;
+    use crate::tests::helpers::cache_without_retries;
+    use crate::{
+        ApiCache, BreakerState, CachePolicy, CacheStatus, CircuitBreakerConfig, RateLimitConfig,
+        UpstreamClient, UpstreamConfig, latest_release_tag,
+    };
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
     use std::time::Duration;
     use tokio::time::sleep;
     use wiremock::{
@@ -8,6 +16,41 @@ mod tests {
         matchers::{method, path},
     };
 
+    /// Serve a single HTTP 200 response with `body`, then stop listening so
+    /// that further requests fail with a connection error. Returns the base URL.
+    fn serve_once(body: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request);
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+        });
+        format!("http://{}", addr)
+    }
+
+    /// Accept connections and close them immediately so every request fails
+    /// with a transport error. Returns the base URL and the connection count.
+    fn serve_broken() -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let connections = Arc::new(AtomicUsize::new(0));
+        let counter = connections.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                counter.fetch_add(1, Ordering::SeqCst);
+                drop(stream);
+            }
+        });
+        (format!("http://{}", addr), connections)
+    }
+
     /// Helper function to create a test cache with short durations
     fn create_test_cache() -> ApiCache {
         ApiCache::new(10) // 10 second cache expiry
@@ -244,4 +287,992 @@ mod tests {
         assert_eq!(result2.1, 201);
         assert_eq!(result2.0, "created");
     }
+
+    #[tokio::test]
+    async fn test_stale_while_revalidate_serves_stale_then_refreshes() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/stale.php"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("data_v1"))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+
+        // 1 second expiry, expired entries may be served for 10 more seconds
+        let cache = ApiCache::new(1).with_stale_window(10);
+        let url = format!("{}/stale.php", mock_server.uri());
+
+        let result1 = cache.get_cached_response(&url).await.unwrap();
+        assert_eq!(result1.0, "data_v1");
+
+        sleep(Duration::from_millis(1100)).await;
+
+        Mock::given(method("GET"))
+            .and(path("/stale.php"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("data_v2"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        // Expired but within the stale window - old data is returned immediately
+        let result2 = cache.get_cached_response(&url).await.unwrap();
+        assert_eq!(result2.0, "data_v1");
+
+        // A second stale read must not start another refresh
+        let result3 = cache.get_cached_response(&url).await.unwrap();
+        assert_eq!(result3.0, "data_v1");
+
+        // Give the background refresh time to complete
+        sleep(Duration::from_millis(300)).await;
+
+        let result4 = cache.get_cached_response(&url).await.unwrap();
+        assert_eq!(result4.0, "data_v2");
+    }
+
+    #[tokio::test]
+    async fn test_entry_beyond_stale_window_is_fetched_synchronously() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/too_old.php"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("data_v1"))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+
+        let cache = ApiCache::new(1).with_stale_window(1);
+        let url = format!("{}/too_old.php", mock_server.uri());
+
+        let result1 = cache.get_cached_response(&url).await.unwrap();
+        assert_eq!(result1.0, "data_v1");
+
+        sleep(Duration::from_millis(2100)).await;
+
+        Mock::given(method("GET"))
+            .and(path("/too_old.php"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("data_v2"))
+            .mount(&mock_server)
+            .await;
+
+        let result2 = cache.get_cached_response(&url).await.unwrap();
+        assert_eq!(result2.0, "data_v2");
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_misses_share_single_request() {
+        let mock_server = MockServer::start().await;
+
+        // Slow upstream so that all callers arrive while the first fetch is in flight
+        Mock::given(method("GET"))
+            .and(path("/coalesced"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string("coalesced_data")
+                    .set_delay(Duration::from_millis(300)),
+            )
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let cache = create_test_cache();
+        let url = format!("{}/coalesced", mock_server.uri());
+
+        let handles: Vec<_> = (0..50)
+            .map(|_| {
+                let cache = cache.clone();
+                let url = url.clone();
+                tokio::spawn(async move { cache.get_cached_response(&url).await })
+            })
+            .collect();
+
+        for handle in handles {
+            let result = handle.await.unwrap().unwrap();
+            assert_eq!(result.0, "coalesced_data");
+            assert_eq!(result.1, 200);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_misses_for_different_urls_are_not_coalesced() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/page"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string("page_data")
+                    .set_delay(Duration::from_millis(200)),
+            )
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+
+        let cache = create_test_cache();
+        let url1 = format!("{}/page?start=0", mock_server.uri());
+        let url2 = format!("{}/page?start=100", mock_server.uri());
+
+        let (result1, result2) = tokio::join!(
+            cache.get_cached_response(&url1),
+            cache.get_cached_response(&url2)
+        );
+        assert_eq!(result1.unwrap().0, "page_data");
+        assert_eq!(result2.unwrap().0, "page_data");
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_misses_share_upstream_error() {
+        // Nothing listens on this port, so the shared fetch fails
+        let cache = create_test_cache();
+        let url = "http://127.0.0.1:1/unreachable";
+
+        let handles: Vec<_> = (0..10)
+            .map(|_| {
+                let cache = cache.clone();
+                tokio::spawn(async move { cache.get_cached_response(url).await })
+            })
+            .collect();
+
+        for handle in handles {
+            let result = handle.await.unwrap();
+            assert!(result.unwrap_err().contains("Request failed"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_caller_does_not_abort_shared_fetch() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/cancelled"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string("survived")
+                    .set_delay(Duration::from_millis(300)),
+            )
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let cache = create_test_cache();
+        let url = format!("{}/cancelled", mock_server.uri());
+
+        // The first caller gives up before the upstream answers
+        let first =
+            tokio::time::timeout(Duration::from_millis(50), cache.get_cached_response(&url)).await;
+        assert!(first.is_err());
+
+        // A later caller joins the fetch that is still running
+        let result = cache.get_cached_response(&url).await.unwrap();
+        assert_eq!(result.0, "survived");
+    }
+
+    #[tokio::test]
+    async fn test_lru_eviction_by_entry_count() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/a"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("a"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        // "b" is evicted and therefore fetched twice
+        Mock::given(method("GET"))
+            .and(path("/b"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("b"))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/c"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("c"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let cache = create_test_cache().with_limits(2, 0);
+        let url_a = format!("{}/a", mock_server.uri());
+        let url_b = format!("{}/b", mock_server.uri());
+        let url_c = format!("{}/c", mock_server.uri());
+
+        cache.get_cached_response(&url_a).await.unwrap();
+        cache.get_cached_response(&url_b).await.unwrap();
+        // Touch "a" so that "b" becomes the least recently used entry
+        cache.get_cached_response(&url_a).await.unwrap();
+        cache.get_cached_response(&url_c).await.unwrap();
+        assert_eq!(cache.len().await, 2);
+
+        // "a" is still cached, "b" has to be fetched again
+        cache.get_cached_response(&url_a).await.unwrap();
+        let result = cache.get_cached_response(&url_b).await.unwrap();
+        assert_eq!(result.0, "b");
+        assert_eq!(cache.len().await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_lru_eviction_by_byte_budget() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/big"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("x".repeat(100)))
+            .mount(&mock_server)
+            .await;
+
+        let base = format!("{}/big", mock_server.uri());
+        let entry_size = format!("{}?page=0", base).len() + 100;
+        // Room for two entries but not three
+        let cache = create_test_cache().with_limits(0, entry_size * 2 + entry_size / 2);
+
+        for page in 0..3 {
+            let url = format!("{}?page={}", base, page);
+            cache.get_cached_response(&url).await.unwrap();
+        }
+
+        assert_eq!(cache.len().await, 2);
+        assert_eq!(cache.size_bytes().await, entry_size * 2);
+    }
+
+    #[tokio::test]
+    async fn test_response_larger_than_budget_is_not_cached() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/huge"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("x".repeat(1000)))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+
+        let cache = create_test_cache().with_limits(0, 100);
+        let url = format!("{}/huge", mock_server.uri());
+
+        // Still returned to the caller, just never stored
+        let result = cache.get_cached_response(&url).await.unwrap();
+        assert_eq!(result.0.len(), 1000);
+        cache.get_cached_response(&url).await.unwrap();
+        assert!(cache.is_empty().await);
+        assert_eq!(cache.size_bytes().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_sweep_expired_removes_old_entries() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/sweep"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("sweep_data"))
+            .mount(&mock_server)
+            .await;
+
+        let cache = ApiCache::new(1);
+        let url = format!("{}/sweep", mock_server.uri());
+        cache.get_cached_response(&url).await.unwrap();
+
+        // Nothing to sweep while the entry is fresh
+        assert_eq!(cache.sweep_expired().await, 0);
+        assert_eq!(cache.len().await, 1);
+
+        sleep(Duration::from_millis(1100)).await;
+
+        assert_eq!(cache.sweep_expired().await, 1);
+        assert!(cache.is_empty().await);
+        assert_eq!(cache.size_bytes().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_sweep_keeps_entries_within_stale_window() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/sweep_stale"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("stale_data"))
+            .mount(&mock_server)
+            .await;
+
+        let cache = ApiCache::new(1).with_stale_window(10);
+        let url = format!("{}/sweep_stale", mock_server.uri());
+        cache.get_cached_response(&url).await.unwrap();
+
+        sleep(Duration::from_millis(1100)).await;
+
+        // Expired but still servable as stale data
+        assert_eq!(cache.sweep_expired().await, 0);
+        assert_eq!(cache.len().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_background_sweeper_drops_expired_entries() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/sweeper"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("sweeper_data"))
+            .mount(&mock_server)
+            .await;
+
+        let cache = ApiCache::new(1);
+        let url = format!("{}/sweeper", mock_server.uri());
+        cache.get_cached_response(&url).await.unwrap();
+
+        let sweeper = cache.spawn_sweeper(Duration::from_millis(200));
+        sleep(Duration::from_millis(1500)).await;
+        sweeper.abort();
+
+        assert!(cache.is_empty().await);
+    }
+
+    #[tokio::test]
+    async fn test_stale_if_error_on_server_error() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/flaky"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("good_data"))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+
+        let cache = cache_without_retries(1).with_stale_if_error(60);
+        let url = format!("{}/flaky", mock_server.uri());
+
+        let result1 = cache.lookup(&url).await.unwrap();
+        assert_eq!(result1.status, CacheStatus::Miss);
+
+        sleep(Duration::from_millis(1100)).await;
+
+        Mock::given(method("GET"))
+            .and(path("/flaky"))
+            .respond_with(ResponseTemplate::new(503).set_body_string("maintenance"))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+
+        // The upstream error is replaced by the last good body
+        let result2 = cache.lookup(&url).await.unwrap();
+        assert_eq!(result2.data, "good_data");
+        assert_eq!(result2.status_code, 200);
+        assert_eq!(result2.status, CacheStatus::StaleIfError);
+        assert!(result2.is_stale());
+        assert!(result2.age >= Duration::from_secs(1));
+
+        // The error body must not have replaced the good entry
+        let result3 = cache.lookup(&url).await.unwrap();
+        assert_eq!(result3.data, "good_data");
+        assert_eq!(result3.status, CacheStatus::StaleIfError);
+    }
+
+    #[tokio::test]
+    async fn test_stale_if_error_on_transport_error() {
+        // Upstream that answers once and then refuses all connections
+        let url = format!("{}/down", serve_once("last_good"));
+
+        let cache = ApiCache::new(1).with_stale_if_error(60);
+        cache.lookup(&url).await.unwrap();
+
+        sleep(Duration::from_millis(1100)).await;
+
+        let result = cache.lookup(&url).await.unwrap();
+        assert_eq!(result.data, "last_good");
+        assert_eq!(result.status, CacheStatus::StaleIfError);
+    }
+
+    #[tokio::test]
+    async fn test_stale_if_error_respects_max_staleness() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/gone"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("old_data"))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+
+        let cache = ApiCache::new(1).with_stale_if_error(1);
+        let url = format!("{}/gone", mock_server.uri());
+        cache.lookup(&url).await.unwrap();
+
+        sleep(Duration::from_millis(2100)).await;
+
+        Mock::given(method("GET"))
+            .and(path("/gone"))
+            .respond_with(ResponseTemplate::new(500).set_body_string("error"))
+            .mount(&mock_server)
+            .await;
+
+        // Too old to be used as a fallback, so the error is passed through
+        let result = cache.lookup(&url).await.unwrap();
+        assert_eq!(result.data, "error");
+        assert_eq!(result.status_code, 500);
+        assert_eq!(result.status, CacheStatus::Miss);
+    }
+
+    #[tokio::test]
+    async fn test_stale_if_error_disabled_by_default() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/no_fallback"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("good_data"))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+
+        let cache = ApiCache::new(1);
+        let url = format!("{}/no_fallback", mock_server.uri());
+        cache.lookup(&url).await.unwrap();
+
+        sleep(Duration::from_millis(1100)).await;
+
+        Mock::given(method("GET"))
+            .and(path("/no_fallback"))
+            .respond_with(ResponseTemplate::new(500).set_body_string("error"))
+            .mount(&mock_server)
+            .await;
+
+        let result = cache.lookup(&url).await.unwrap();
+        assert_eq!(result.status_code, 500);
+    }
+
+    #[tokio::test]
+    async fn test_lookup_reports_cache_status() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/status"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("status_data"))
+            .mount(&mock_server)
+            .await;
+
+        let cache = ApiCache::new(1).with_stale_window(10);
+        let url = format!("{}/status", mock_server.uri());
+
+        assert_eq!(cache.lookup(&url).await.unwrap().status, CacheStatus::Miss);
+        assert_eq!(cache.lookup(&url).await.unwrap().status, CacheStatus::Hit);
+
+        sleep(Duration::from_millis(1100)).await;
+
+        let stale = cache.lookup(&url).await.unwrap();
+        assert_eq!(stale.status, CacheStatus::Stale);
+        assert!(stale.is_stale());
+    }
+
+    #[tokio::test]
+    async fn test_error_status_cached_for_negative_ttl() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/missing"))
+            .respond_with(ResponseTemplate::new(404).set_body_string("not found"))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+
+        // Errors are cached for 1 second even though the normal TTL is 10
+        let cache = create_test_cache();
+        let policy = CachePolicy::new(10, 1);
+        let url = format!("{}/missing", mock_server.uri());
+
+        for _ in 0..3 {
+            let result = cache.lookup_with_policy(&url, policy).await.unwrap();
+            assert_eq!(result.status_code, 404);
+            assert_eq!(result.data, "not found");
+        }
+
+        sleep(Duration::from_millis(1100)).await;
+
+        // Negative entry expired, upstream is asked again
+        let result = cache.lookup_with_policy(&url, policy).await.unwrap();
+        assert_eq!(result.status_code, 404);
+        assert_eq!(result.status, CacheStatus::Miss);
+    }
+
+    #[tokio::test]
+    async fn test_errors_not_cached_without_negative_ttl() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/error"))
+            .respond_with(ResponseTemplate::new(500).set_body_string("error"))
+            .expect(3)
+            .mount(&mock_server)
+            .await;
+
+        let cache = cache_without_retries(10);
+        let url = format!("{}/error", mock_server.uri());
+
+        for _ in 0..3 {
+            let result = cache.get_cached_response(&url).await.unwrap();
+            assert_eq!(result.1, 500);
+        }
+        assert!(cache.is_empty().await);
+    }
+
+    #[tokio::test]
+    async fn test_transport_error_cached_for_negative_ttl() {
+        let (base, connections) = serve_broken();
+        let url = format!("{}/broken", base);
+
+        let cache = cache_without_retries(10);
+        let policy = CachePolicy::new(10, 5);
+
+        for _ in 0..3 {
+            let result = cache.lookup_with_policy(&url, policy).await;
+            assert!(result.unwrap_err().contains("Request failed"));
+        }
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_error_does_not_overwrite_good_entry() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/keep"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("good_data"))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+
+        let cache = cache_without_retries(1).with_stale_if_error(60);
+        let policy = CachePolicy::new(1, 5);
+        let url = format!("{}/keep", mock_server.uri());
+        cache.lookup_with_policy(&url, policy).await.unwrap();
+
+        sleep(Duration::from_millis(1100)).await;
+
+        // Only one upstream call: the failure is remembered for the negative TTL
+        Mock::given(method("GET"))
+            .and(path("/keep"))
+            .respond_with(ResponseTemplate::new(500).set_body_string("error"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        for _ in 0..3 {
+            let result = cache.lookup_with_policy(&url, policy).await.unwrap();
+            assert_eq!(result.data, "good_data");
+            assert_eq!(result.status_code, 200);
+            assert_eq!(result.status, CacheStatus::StaleIfError);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_negative_entries_are_swept_after_negative_ttl() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/sweep_error"))
+            .respond_with(ResponseTemplate::new(502).set_body_string("bad gateway"))
+            .mount(&mock_server)
+            .await;
+
+        // Long stale windows only apply to successful responses
+        let cache = create_test_cache()
+            .with_stale_window(60)
+            .with_stale_if_error(60)
+            .with_default_policy(CachePolicy::new(10, 1));
+        let url = format!("{}/sweep_error", mock_server.uri());
+        cache.lookup(&url).await.unwrap();
+        assert_eq!(cache.len().await, 1);
+
+        sleep(Duration::from_millis(1100)).await;
+
+        assert_eq!(cache.sweep_expired().await, 1);
+        assert!(cache.is_empty().await);
+    }
+
+    #[tokio::test]
+    async fn test_open_circuit_fails_fast() {
+        let (base, connections) = serve_broken();
+
+        let cache =
+            cache_without_retries(10).with_circuit_breaker(CircuitBreakerConfig::new(2, 30));
+
+        for page in 0..5 {
+            let url = format!("{}/list?start={}", base, page);
+            assert!(cache.lookup(&url).await.is_err());
+        }
+
+        // Two failures open the circuit, the remaining lookups never reach upstream
+        assert_eq!(connections.load(Ordering::SeqCst), 2);
+
+        let status = cache.upstream_status();
+        assert_eq!(status.len(), 1);
+        assert_eq!(status[0].upstream, base);
+        assert_eq!(status[0].state, BreakerState::Open);
+    }
+
+    #[tokio::test]
+    async fn test_open_circuit_serves_stale_data() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/breaker"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("good_data"))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+
+        let cache = cache_without_retries(1)
+            .with_stale_if_error(60)
+            .with_circuit_breaker(CircuitBreakerConfig::new(1, 30));
+        let url = format!("{}/breaker", mock_server.uri());
+        cache.lookup(&url).await.unwrap();
+
+        sleep(Duration::from_millis(1100)).await;
+
+        // One failing request opens the circuit, later ones are not sent
+        Mock::given(method("GET"))
+            .and(path("/breaker"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        for _ in 0..3 {
+            let result = cache.lookup(&url).await.unwrap();
+            assert_eq!(result.data, "good_data");
+            assert_eq!(result.status, CacheStatus::StaleIfError);
+        }
+        assert_eq!(cache.upstream_status()[0].state, BreakerState::Open);
+    }
+
+    #[tokio::test]
+    async fn test_circuit_closes_after_successful_trial() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/recover"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+
+        let cache = cache_without_retries(10).with_circuit_breaker(CircuitBreakerConfig {
+            failure_threshold: 1,
+            cool_down: Duration::from_millis(200),
+        });
+        let url = format!("{}/recover", mock_server.uri());
+
+        assert_eq!(cache.lookup(&url).await.unwrap().status_code, 503);
+        assert!(cache.lookup(&url).await.is_err());
+
+        Mock::given(method("GET"))
+            .and(path("/recover"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("recovered"))
+            .mount(&mock_server)
+            .await;
+        sleep(Duration::from_millis(300)).await;
+
+        let result = cache.lookup(&url).await.unwrap();
+        assert_eq!(result.data, "recovered");
+        assert_eq!(cache.upstream_status()[0].state, BreakerState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_client_errors_do_not_open_circuit() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/not_found"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(3)
+            .mount(&mock_server)
+            .await;
+
+        let cache =
+            cache_without_retries(10).with_circuit_breaker(CircuitBreakerConfig::new(1, 30));
+        let url = format!("{}/not_found", mock_server.uri());
+
+        for _ in 0..3 {
+            assert_eq!(cache.lookup(&url).await.unwrap().status_code, 404);
+        }
+        assert_eq!(cache.upstream_status()[0].state, BreakerState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_answers_503_without_upstream_request() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("limited_data"))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+
+        let cache = cache_without_retries(60)
+            .with_circuit_breaker(CircuitBreakerConfig::new(1, 30))
+            .with_rate_limit(RateLimitConfig::new(0.1, 2, 0, 0));
+
+        // Unique keys bypass the cache, so only the budget protects upstream
+        for page in 0..2 {
+            let url = format!("{}/page/{}", mock_server.uri(), page);
+            assert_eq!(cache.lookup(&url).await.unwrap().status_code, 200);
+        }
+        let url = format!("{}/page/2", mock_server.uri());
+        let limited = cache.lookup(&url).await.unwrap();
+        assert_eq!(limited.status_code, 503);
+        assert!(limited.data.contains("rate limit"));
+
+        // Rejections are neither cached nor held against the upstream
+        assert_eq!(cache.len().await, 2);
+        assert_eq!(cache.upstream_status()[0].state, BreakerState::Closed);
+        assert_eq!(cache.rate_limit_status().rejected, 1);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_serves_stale_data() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/budget"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("budget_data"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let cache = cache_without_retries(1)
+            .with_stale_if_error(60)
+            .with_rate_limit(RateLimitConfig::new(0.1, 1, 0, 0));
+        let url = format!("{}/budget", mock_server.uri());
+        cache.lookup(&url).await.unwrap();
+
+        sleep(Duration::from_millis(1100)).await;
+
+        let result = cache.lookup(&url).await.unwrap();
+        assert_eq!(result.data, "budget_data");
+        assert_eq!(result.status, CacheStatus::StaleIfError);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_counts_every_retry() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/flaky"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+
+        let client = UpstreamClient::new(UpstreamConfig {
+            max_retries: 2,
+            retry_backoff: Duration::from_millis(1),
+            ..UpstreamConfig::default()
+        })
+        .unwrap();
+        let cache = ApiCache::new(60)
+            .with_client(client)
+            .with_rate_limit(RateLimitConfig::new(0.1, 2, 0, 0));
+
+        // The second retry has no token left and is not sent
+        let url = format!("{}/flaky", mock_server.uri());
+        assert_eq!(cache.lookup(&url).await.unwrap().status_code, 500);
+        assert_eq!(cache.rate_limit_status().rejected, 1);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_counts_every_upstream_tried() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/primary"))
+            .respond_with(ResponseTemplate::new(502))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/mirror"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("mirror_data"))
+            .expect(0)
+            .mount(&mock_server)
+            .await;
+
+        let cache = cache_without_retries(60).with_rate_limit(RateLimitConfig::new(0.1, 1, 0, 0));
+        let urls = [
+            format!("{}/primary", mock_server.uri()),
+            format!("{}/mirror", mock_server.uri()),
+        ];
+
+        // The failover has no token left; the primary's response is returned
+        let result = cache
+            .lookup_with_failover("failover", &urls, CachePolicy::new(60, 0))
+            .await
+            .unwrap();
+        assert_eq!(result.status_code, 502);
+        assert_eq!(cache.rate_limit_status().rejected, 1);
+    }
+
+    #[tokio::test]
+    async fn test_failover_spends_retries_on_last_upstream() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/primary"))
+            .respond_with(ResponseTemplate::new(502))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/mirror"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(3)
+            .mount(&mock_server)
+            .await;
+
+        let client = UpstreamClient::new(UpstreamConfig {
+            max_retries: 2,
+            retry_backoff: Duration::from_millis(1),
+            ..UpstreamConfig::default()
+        })
+        .unwrap();
+        let cache = ApiCache::new(60).with_client(client);
+        let urls = [
+            format!("{}/primary", mock_server.uri()),
+            format!("{}/mirror", mock_server.uri()),
+        ];
+
+        // The primary is not retried before failing over to the mirror
+        let result = cache
+            .lookup_with_failover("failover", &urls, CachePolicy::new(60, 0))
+            .await
+            .unwrap();
+        assert_eq!(result.status_code, 503);
+    }
+
+    #[tokio::test]
+    async fn test_ttl_is_taken_from_policy() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/servers"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("servers"))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/players"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("players"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        // One cache, two routes with their own TTLs
+        let cache = create_test_cache();
+        let servers = (
+            format!("{}/servers", mock_server.uri()),
+            CachePolicy::new(1, 0),
+        );
+        let players = (
+            format!("{}/players", mock_server.uri()),
+            CachePolicy::new(60, 0),
+        );
+        for (url, policy) in [&servers, &players] {
+            cache.lookup_with_policy(url, *policy).await.unwrap();
+        }
+
+        sleep(Duration::from_millis(1100)).await;
+
+        let result = cache
+            .lookup_with_policy(&servers.0, servers.1)
+            .await
+            .unwrap();
+        assert_eq!(result.status, CacheStatus::Miss);
+        let result = cache
+            .lookup_with_policy(&players.0, players.1)
+            .await
+            .unwrap();
+        assert_eq!(result.status, CacheStatus::Hit);
+    }
+
+    #[tokio::test]
+    async fn test_entries_count_hits_across_refreshes() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/hits"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("hits_data"))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+
+        let cache = ApiCache::new(1);
+        let url = format!("{}/hits", mock_server.uri());
+        for _ in 0..3 {
+            cache.get_cached_response(&url).await.unwrap();
+        }
+
+        let entries = cache.entries("").await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].key, url);
+        assert_eq!(entries[0].hits, 2);
+        assert!(entries[0].last_hit_secs.is_some());
+
+        // A refresh replaces the entry but keeps its statistics
+        sleep(Duration::from_millis(1100)).await;
+        cache.get_cached_response(&url).await.unwrap();
+        assert_eq!(cache.entries("").await.unwrap()[0].hits, 2);
+    }
+
+    #[tokio::test]
+    async fn test_purge_by_key_and_prefix() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("purge_data"))
+            .mount(&mock_server)
+            .await;
+
+        let cache = create_test_cache();
+        for endpoint in ["/a/1", "/a/2", "/b/1"] {
+            let url = format!("{}{}", mock_server.uri(), endpoint);
+            cache.get_cached_response(&url).await.unwrap();
+        }
+
+        let key = format!("{}/b/1", mock_server.uri());
+        assert!(cache.purge(&key).await.unwrap());
+        assert!(!cache.purge(&key).await.unwrap());
+
+        let prefix = format!("{}/a/", mock_server.uri());
+        assert_eq!(cache.purge_prefix(&prefix).await.unwrap(), 2);
+        assert!(cache.is_empty().await);
+        assert!(cache.entries("").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_latest_release_tag_is_cached() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/repos/owner/repo/releases/latest"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"tag_name": "v1.2.3"}"#))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let cache = create_test_cache();
+        let api_url = format!("{}/repos/owner/repo/releases/latest", mock_server.uri());
+        let policy = CachePolicy::new(300, 60);
+
+        for _ in 0..3 {
+            let tag = latest_release_tag(&cache, &api_url, policy).await;
+            assert_eq!(tag.as_deref(), Some("v1.2.3"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_latest_release_tag_missing_release() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/repos/owner/repo/releases/latest"))
+            .respond_with(ResponseTemplate::new(404).set_body_string(r#"{"message": "Not Found"}"#))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        // The 404 is cached for the negative TTL
+        let cache = create_test_cache();
+        let api_url = format!("{}/repos/owner/repo/releases/latest", mock_server.uri());
+        let policy = CachePolicy::new(300, 60);
+
+        for _ in 0..2 {
+            assert!(latest_release_tag(&cache, &api_url, policy).await.is_none());
+        }
+    }
 }
@@ -0,0 +1,104 @@
+#[cfg(test)]
+mod tests {
+    use crate::{BreakerState, CircuitBreaker, CircuitBreakerConfig};
+    use std::time::Duration;
+    use tokio::time::sleep;
+
+    fn breaker(failure_threshold: u32, cool_down: Duration) -> CircuitBreaker {
+        CircuitBreaker::new(
+            "http://upstream.test",
+            CircuitBreakerConfig {
+                failure_threshold,
+                cool_down,
+            },
+        )
+    }
+
+    #[test]
+    fn test_opens_after_threshold() {
+        let breaker = breaker(3, Duration::from_secs(30));
+
+        breaker.record_failure("error 1");
+        breaker.record_failure("error 2");
+        assert_eq!(breaker.state(), BreakerState::Closed);
+        assert!(breaker.try_acquire().is_ok());
+
+        breaker.record_failure("error 3");
+        assert_eq!(breaker.state(), BreakerState::Open);
+        assert!(breaker.try_acquire().unwrap_err().contains("open"));
+
+        let status = breaker.status();
+        assert_eq!(status.consecutive_failures, 3);
+        assert_eq!(status.last_error.as_deref(), Some("error 3"));
+        assert!(status.retry_in_secs.is_some());
+    }
+
+    #[test]
+    fn test_success_resets_failure_count() {
+        let breaker = breaker(2, Duration::from_secs(30));
+
+        breaker.record_failure("error");
+        breaker.record_success();
+        breaker.record_failure("error");
+        assert_eq!(breaker.state(), BreakerState::Closed);
+        assert_eq!(breaker.status().consecutive_failures, 1);
+    }
+
+    #[tokio::test]
+    async fn test_half_open_trial_closes_on_success() {
+        let breaker = breaker(1, Duration::from_millis(100));
+
+        breaker.record_failure("error");
+        assert!(breaker.try_acquire().is_err());
+
+        sleep(Duration::from_millis(150)).await;
+
+        // Only one trial request is let through
+        assert!(breaker.try_acquire().is_ok());
+        assert_eq!(breaker.state(), BreakerState::HalfOpen);
+        assert!(breaker.try_acquire().is_err());
+
+        breaker.record_success();
+        assert_eq!(breaker.state(), BreakerState::Closed);
+        assert!(breaker.try_acquire().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_half_open_trial_reopens_on_failure() {
+        let breaker = breaker(1, Duration::from_millis(100));
+
+        breaker.record_failure("error");
+        sleep(Duration::from_millis(150)).await;
+
+        assert!(breaker.try_acquire().is_ok());
+        breaker.record_failure("still failing");
+        assert_eq!(breaker.state(), BreakerState::Open);
+        assert!(breaker.try_acquire().is_err());
+    }
+
+    #[test]
+    fn test_released_trial_can_be_retried() {
+        let breaker = breaker(1, Duration::ZERO);
+        breaker.record_failure("error");
+
+        assert!(breaker.try_acquire().is_ok());
+        assert_eq!(breaker.state(), BreakerState::HalfOpen);
+        assert!(breaker.try_acquire().is_err());
+
+        // The trial was never sent, so another request may take its place
+        breaker.release();
+        assert!(breaker.try_acquire().is_ok());
+        assert_eq!(breaker.state(), BreakerState::HalfOpen);
+    }
+
+    #[test]
+    fn test_disabled_breaker_never_opens() {
+        let breaker = CircuitBreaker::new("http://upstream.test", CircuitBreakerConfig::disabled());
+
+        for _ in 0..10 {
+            breaker.record_failure("error");
+        }
+        assert_eq!(breaker.state(), BreakerState::Closed);
+        assert!(breaker.try_acquire().is_ok());
+    }
+}
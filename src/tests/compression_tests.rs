@@ -0,0 +1,90 @@
+#[cfg(test)]
+mod tests {
+    use crate::{CompressedBodies, ContentEncoding};
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    fn large_body() -> String {
+        "<server><name>Test</name><players>8</players></server>".repeat(50)
+    }
+
+    #[test]
+    fn test_small_bodies_are_not_compressed() {
+        let bodies = CompressedBodies::compress("<result></result>");
+        assert!(bodies.get(ContentEncoding::Gzip).is_none());
+        assert!(bodies.get(ContentEncoding::Brotli).is_none());
+        assert_eq!(bodies.size_bytes(), 0);
+        assert_eq!(bodies.negotiate("gzip, br"), ContentEncoding::Identity);
+    }
+
+    #[test]
+    fn test_encodings_decode_to_body() {
+        let body = large_body();
+        let bodies = CompressedBodies::compress(&body);
+
+        let gzip = bodies.get(ContentEncoding::Gzip).unwrap();
+        assert!(gzip.len() < body.len());
+        let mut decoded = String::new();
+        GzDecoder::new(&gzip[..])
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, body);
+
+        let brotli = bodies.get(ContentEncoding::Brotli).unwrap();
+        assert!(brotli.len() < body.len());
+        let mut decoded = String::new();
+        brotli::Decompressor::new(&brotli[..], 4096)
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, body);
+    }
+
+    #[test]
+    fn test_negotiate_accept_encoding() {
+        let bodies = CompressedBodies::compress(&large_body());
+
+        assert_eq!(
+            bodies.negotiate("gzip, deflate, br"),
+            ContentEncoding::Brotli
+        );
+        assert_eq!(bodies.negotiate("gzip"), ContentEncoding::Gzip);
+        assert_eq!(bodies.negotiate("GZIP"), ContentEncoding::Gzip);
+        assert_eq!(bodies.negotiate("br;q=0, gzip"), ContentEncoding::Gzip);
+        assert_eq!(
+            bodies.negotiate("gzip;q=0.8, br;q=0.5"),
+            ContentEncoding::Gzip
+        );
+        assert_eq!(bodies.negotiate("*"), ContentEncoding::Brotli);
+        assert_eq!(bodies.negotiate("*;q=0, gzip"), ContentEncoding::Gzip);
+        assert_eq!(bodies.negotiate("identity"), ContentEncoding::Identity);
+        assert_eq!(bodies.negotiate(""), ContentEncoding::Identity);
+    }
+
+    #[test]
+    fn test_tag_etag() {
+        assert_eq!(ContentEncoding::Identity.tag_etag("\"abc\""), "\"abc\"");
+        assert_eq!(ContentEncoding::Gzip.tag_etag("\"abc\""), "\"abc-gzip\"");
+        assert_eq!(ContentEncoding::Brotli.tag_etag("\"abc\""), "\"abc-br\"");
+    }
+
+    #[test]
+    fn test_encodings_survive_serialization() {
+        let body = large_body();
+        let bodies = CompressedBodies::compress(&body);
+
+        let json = serde_json::to_string(&bodies).unwrap();
+        let restored: CompressedBodies = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            restored.get(ContentEncoding::Gzip),
+            bodies.get(ContentEncoding::Gzip)
+        );
+        assert_eq!(
+            restored.get(ContentEncoding::Brotli),
+            bodies.get(ContentEncoding::Brotli)
+        );
+
+        // Entries stored without encodings still load
+        let empty: CompressedBodies = serde_json::from_str("{}").unwrap();
+        assert_eq!(empty.size_bytes(), 0);
+    }
+}
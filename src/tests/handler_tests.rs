@@ -0,0 +1,817 @@
+#[cfg(test)]
+mod tests {
+    use crate::handlers::{AppState, create_router};
+    use crate::tests::helpers::{cache_without_retries, test_config};
+    use crate::{Config, MapsConfig, PopularQueries, ServerSnapshots};
+    use salvo::http::header;
+    use salvo::prelude::*;
+    use salvo::test::{ResponseExt, TestClient};
+    use std::sync::Arc;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{method, path, query_param},
+    };
+
+    /// Service whose proxied routes use the given upstream base URLs
+    fn test_service(
+        server_list_upstreams: Vec<String>,
+        player_list_upstreams: Vec<String>,
+    ) -> Service {
+        let mut config = test_config();
+        config.server_list_upstreams = server_list_upstreams;
+        config.player_list_upstreams = player_list_upstreams;
+        service_with_config(config)
+    }
+
+    fn service_with_config(config: Config) -> Service {
+        let cache = Arc::new(
+            cache_without_retries(10)
+                .with_stale_if_error(60)
+                .with_compression(config.cache_compression)
+                .with_rate_limit(config.rate_limit_config()),
+        );
+
+        let state = AppState {
+            server_snapshots: Arc::new(ServerSnapshots::new(
+                cache.clone(),
+                config.server_list_route(),
+                config.server_snapshot_config(),
+            )),
+            config: Arc::new(config),
+            cache,
+            maps_config: Arc::new(MapsConfig::new()),
+            popular_server_queries: Arc::new(PopularQueries::new()),
+        };
+        Service::new(create_router(&state))
+    }
+
+    #[tokio::test]
+    async fn test_ping() {
+        let service = test_service(vec![], vec![]);
+        let mut res = TestClient::get("http://127.0.0.1:5800/ping")
+            .send(&service)
+            .await;
+        assert_eq!(res.take_string().await.unwrap(), "pong");
+    }
+
+    #[tokio::test]
+    async fn test_server_list_forwards_canonical_query() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/get_server_list.php"))
+            .and(query_param("start", "0"))
+            .and(query_param("size", "100"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("<result></result>"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let service = test_service(
+            vec![format!("{}/get_server_list.php", mock_server.uri())],
+            vec![],
+        );
+
+        // Equivalent queries share one cache entry and one upstream request
+        for query in [
+            "start=0&size=100",
+            "size=100&start=0",
+            "size=100&start=0&_=1",
+        ] {
+            let mut res =
+                TestClient::get(format!("http://127.0.0.1:5800/api/server_list?{}", query))
+                    .send(&service)
+                    .await;
+            assert_eq!(res.status_code, Some(StatusCode::OK));
+            assert_eq!(res.take_string().await.unwrap(), "<result></result>");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_server_list_fails_over_to_mirror() {
+        let primary = MockServer::start().await;
+        let mirror = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/get_server_list.php"))
+            .respond_with(ResponseTemplate::new(502))
+            .expect(1)
+            .mount(&primary)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/get_server_list.php"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("mirror_list"))
+            .expect(1)
+            .mount(&mirror)
+            .await;
+
+        let service = test_service(
+            vec![
+                format!("{}/get_server_list.php", primary.uri()),
+                format!("{}/get_server_list.php", mirror.uri()),
+            ],
+            vec![],
+        );
+
+        let mut res = TestClient::get("http://127.0.0.1:5800/api/server_list?start=0")
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+        assert_eq!(res.take_string().await.unwrap(), "mirror_list");
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_primary_fails_over_to_mirror() {
+        let mirror = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/view_players.php"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("players"))
+            .mount(&mirror)
+            .await;
+
+        let service = test_service(
+            vec![],
+            vec![
+                "http://127.0.0.1:1/view_players.php".to_string(),
+                format!("{}/view_players.php", mirror.uri()),
+            ],
+        );
+
+        let mut res = TestClient::get("http://127.0.0.1:5800/api/player_list?sort=kills")
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+        assert_eq!(res.take_string().await.unwrap(), "players");
+    }
+
+    #[tokio::test]
+    async fn test_all_upstreams_failing_returns_error() {
+        let service = test_service(
+            vec![
+                "http://127.0.0.1:1/get_server_list.php".to_string(),
+                "http://127.0.0.1:2/get_server_list.php".to_string(),
+            ],
+            vec![],
+        );
+
+        let mut res = TestClient::get("http://127.0.0.1:5800/api/server_list")
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::INTERNAL_SERVER_ERROR));
+        assert!(
+            res.take_string()
+                .await
+                .unwrap()
+                .contains("Unable to fetch server list")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_player_list_client_error_is_not_failed_over() {
+        let primary = MockServer::start().await;
+        let mirror = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/view_players.php"))
+            .respond_with(ResponseTemplate::new(404).set_body_string("no such player"))
+            .expect(1)
+            .mount(&primary)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/view_players.php"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&mirror)
+            .await;
+
+        let service = test_service(
+            vec![],
+            vec![
+                format!("{}/view_players.php", primary.uri()),
+                format!("{}/view_players.php", mirror.uri()),
+            ],
+        );
+
+        let res = TestClient::get("http://127.0.0.1:5800/api/player_list?search=nobody")
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::NOT_FOUND));
+        assert!(res.headers().get(header::ETAG).is_none());
+    }
+
+    fn header_value(res: &Response, name: header::HeaderName) -> String {
+        res.headers()
+            .get(name)
+            .expect("missing header")
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_server_list_if_none_match() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/get_server_list.php"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("<result></result>"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let service = test_service(
+            vec![format!("{}/get_server_list.php", mock_server.uri())],
+            vec![],
+        );
+        let url = "http://127.0.0.1:5800/api/server_list?start=0";
+
+        let res = TestClient::get(url).send(&service).await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+        let etag = header_value(&res, header::ETAG);
+        assert!(etag.starts_with('"') && etag.ends_with('"'));
+        assert!(res.headers().contains_key(header::LAST_MODIFIED));
+
+        // The cached entry has the same ETag as the response to the miss
+        let mut res = TestClient::get(url)
+            .add_header(header::IF_NONE_MATCH, format!("\"other\", {}", etag), true)
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::NOT_MODIFIED));
+        assert_eq!(header_value(&res, header::ETAG), etag);
+        assert!(res.take_string().await.unwrap().is_empty());
+
+        let mut res = TestClient::get(url)
+            .add_header(header::IF_NONE_MATCH, "\"other\"", true)
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+        assert_eq!(res.take_string().await.unwrap(), "<result></result>");
+    }
+
+    #[tokio::test]
+    async fn test_player_list_if_modified_since() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/view_players.php"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("players"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let service = test_service(
+            vec![],
+            vec![format!("{}/view_players.php", mock_server.uri())],
+        );
+        let url = "http://127.0.0.1:5800/api/player_list";
+
+        let res = TestClient::get(url).send(&service).await;
+        let last_modified = header_value(&res, header::LAST_MODIFIED);
+
+        let res = TestClient::get(url)
+            .add_header(header::IF_MODIFIED_SINCE, last_modified, true)
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::NOT_MODIFIED));
+
+        let mut res = TestClient::get(url)
+            .add_header(
+                header::IF_MODIFIED_SINCE,
+                "Sat, 01 Jan 2000 00:00:00 GMT",
+                true,
+            )
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+        assert_eq!(res.take_string().await.unwrap(), "players");
+    }
+
+    #[tokio::test]
+    async fn test_maps_if_none_match() {
+        let service = test_service(vec![], vec![]);
+        let url = "http://127.0.0.1:5800/api/maps";
+
+        let mut res = TestClient::get(url).send(&service).await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+        let etag = header_value(&res, header::ETAG);
+        assert_eq!(res.take_string().await.unwrap(), "[]");
+
+        let res = TestClient::get(url)
+            .add_header(header::IF_NONE_MATCH, format!("W/{}", etag), true)
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::NOT_MODIFIED));
+    }
+
+    #[tokio::test]
+    async fn test_server_list_cache_headers() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/get_server_list.php"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("<result></result>"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let mut config = test_config();
+        config.server_list_upstreams = vec![format!("{}/get_server_list.php", mock_server.uri())];
+        config.server_list_cache_secs = 30;
+        let service = service_with_config(config);
+        let url = "http://127.0.0.1:5800/api/server_list?start=0";
+
+        let res = TestClient::get(url).send(&service).await;
+        assert_eq!(header_value(&res, "x-cache".parse().unwrap()), "MISS");
+        assert_eq!(header_value(&res, header::AGE), "0");
+        assert!((29..=30).contains(&max_age(&res)));
+
+        let res = TestClient::get(url).send(&service).await;
+        assert_eq!(header_value(&res, "x-cache".parse().unwrap()), "HIT");
+        assert!((29..=30).contains(&max_age(&res)));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited_response_is_not_cacheable() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/get_server_list.php"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("<result></result>"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let mut config = test_config();
+        config.server_list_upstreams = vec![format!("{}/get_server_list.php", mock_server.uri())];
+        config.upstream_rate_limit = 0.001;
+        config.upstream_rate_limit_burst = 1;
+        config.upstream_rate_limit_wait_ms = 0;
+        let service = service_with_config(config);
+
+        TestClient::get("http://127.0.0.1:5800/api/server_list?start=0")
+            .send(&service)
+            .await;
+        let res = TestClient::get("http://127.0.0.1:5800/api/server_list?start=100")
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::SERVICE_UNAVAILABLE));
+        assert_eq!(header_value(&res, header::CACHE_CONTROL), "no-store");
+    }
+
+    fn max_age(res: &Response) -> u64 {
+        header_value(res, header::CACHE_CONTROL)
+            .strip_prefix("public, max-age=")
+            .expect("unexpected Cache-Control")
+            .parse()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_stale_response_cache_headers() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/get_server_list.php"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("<result></result>"))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/get_server_list.php"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&mock_server)
+            .await;
+
+        // Responses expire immediately, so the second request goes upstream
+        let mut config = test_config();
+        config.server_list_upstreams = vec![format!("{}/get_server_list.php", mock_server.uri())];
+        config.server_list_cache_secs = 0;
+        let service = service_with_config(config);
+        let url = "http://127.0.0.1:5800/api/server_list?start=0";
+
+        TestClient::get(url).send(&service).await;
+        let mut res = TestClient::get(url).send(&service).await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+        assert_eq!(header_value(&res, "x-cache".parse().unwrap()), "STALE");
+        assert_eq!(
+            header_value(&res, header::CACHE_CONTROL),
+            "public, max-age=0"
+        );
+        assert!(res.headers().contains_key(header::WARNING));
+        assert_eq!(res.take_string().await.unwrap(), "<result></result>");
+    }
+
+    #[tokio::test]
+    async fn test_server_list_serves_precompressed_body() {
+        let body = "<server><name>Test</name></server>".repeat(100);
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/get_server_list.php"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(body.clone()))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let service = test_service(
+            vec![format!("{}/get_server_list.php", mock_server.uri())],
+            vec![],
+        );
+        let url = "http://127.0.0.1:5800/api/server_list?start=0";
+
+        let mut res = TestClient::get(url)
+            .add_header(header::ACCEPT_ENCODING, "gzip, deflate, br", true)
+            .send(&service)
+            .await;
+        assert_eq!(header_value(&res, header::CONTENT_ENCODING), "br");
+        assert_eq!(header_value(&res, header::VARY), "accept-encoding");
+        assert!(header_value(&res, header::ETAG).ends_with("-br\""));
+        assert_eq!(res.take_string().await.unwrap(), body);
+
+        let mut res = TestClient::get(url)
+            .add_header(header::ACCEPT_ENCODING, "gzip", true)
+            .send(&service)
+            .await;
+        assert_eq!(header_value(&res, header::CONTENT_ENCODING), "gzip");
+        assert_eq!(res.take_string().await.unwrap(), body);
+
+        let mut res = TestClient::get(url).send(&service).await;
+        assert!(res.headers().get(header::CONTENT_ENCODING).is_none());
+        let etag = header_value(&res, header::ETAG);
+        assert_eq!(res.take_string().await.unwrap(), body);
+
+        // The identity ETag does not match the compressed representation
+        let res = TestClient::get(url)
+            .add_header(header::ACCEPT_ENCODING, "br", true)
+            .add_header(header::IF_NONE_MATCH, etag, true)
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+    }
+
+    fn admin_service(server_list_upstream: String) -> Service {
+        let mut config = test_config();
+        config.server_list_upstreams = vec![server_list_upstream];
+        config.admin_token = Some("secret".to_string());
+        service_with_config(config)
+    }
+
+    #[tokio::test]
+    async fn test_admin_disabled_without_token() {
+        let mut config = test_config();
+        config.admin_token = None;
+        let service = service_with_config(config);
+
+        let res = TestClient::get("http://127.0.0.1:5800/api/admin/cache")
+            .add_header(header::AUTHORIZATION, "Bearer secret", true)
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::NOT_FOUND));
+    }
+
+    #[tokio::test]
+    async fn test_admin_requires_bearer_token() {
+        let service = admin_service("http://127.0.0.1:1/get_server_list.php".to_string());
+        let url = "http://127.0.0.1:5800/api/admin/cache";
+
+        let res = TestClient::get(url).send(&service).await;
+        assert_eq!(res.status_code, Some(StatusCode::UNAUTHORIZED));
+        assert_eq!(header_value(&res, header::WWW_AUTHENTICATE), "Bearer");
+
+        let res = TestClient::delete(url)
+            .add_header(header::AUTHORIZATION, "Bearer wrong", true)
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::UNAUTHORIZED));
+
+        let res = TestClient::get(url)
+            .add_header(header::AUTHORIZATION, "Bearer secret", true)
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+    }
+
+    #[tokio::test]
+    async fn test_admin_lists_and_purges_cache() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/get_server_list.php"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("<result></result>"))
+            .expect(3)
+            .mount(&mock_server)
+            .await;
+        let service = admin_service(format!("{}/get_server_list.php", mock_server.uri()));
+        let admin_url = "http://127.0.0.1:5800/api/admin/cache";
+
+        // One miss and one hit for the first page, a miss for the second
+        for start in [0, 0, 100] {
+            TestClient::get(format!(
+                "http://127.0.0.1:5800/api/server_list?start={}",
+                start
+            ))
+            .send(&service)
+            .await;
+        }
+
+        let mut res = TestClient::get(admin_url)
+            .add_header(header::AUTHORIZATION, "Bearer secret", true)
+            .send(&service)
+            .await;
+        let listing: serde_json::Value = res.take_json().await.unwrap();
+        assert_eq!(listing["count"], 2);
+        let first = &listing["entries"][0];
+        assert!(first["key"].as_str().unwrap().contains("start=0"));
+        assert_eq!(first["status_code"], 200);
+        assert_eq!(first["hits"], 1);
+        assert_eq!(first["fresh"], true);
+        assert!(first["size_bytes"].as_u64().unwrap() > 0);
+        assert_eq!(listing["entries"][1]["hits"], 0);
+        assert!(listing["entries"][1]["last_hit_secs"].is_null());
+
+        // Purge the first page by its key
+        let key = first["key"].as_str().unwrap().to_string();
+        let encoded_key: String = form_urlencoded::byte_serialize(key.as_bytes()).collect();
+        let mut res = TestClient::delete(format!("{}?key={}", admin_url, encoded_key))
+            .add_header(header::AUTHORIZATION, "Bearer secret", true)
+            .send(&service)
+            .await;
+        let purged: serde_json::Value = res.take_json().await.unwrap();
+        assert_eq!(purged["purged"], 1);
+
+        // The purged page is fetched again
+        TestClient::get("http://127.0.0.1:5800/api/server_list?start=0")
+            .send(&service)
+            .await;
+
+        // Purging everything has to be asked for explicitly
+        for query in [
+            "",
+            "?prefix=",
+            "?prefx=server_list",
+            "?all=1",
+            "?all=true&key=a",
+        ] {
+            let res = TestClient::delete(format!("{}{}", admin_url, query))
+                .add_header(header::AUTHORIZATION, "Bearer secret", true)
+                .send(&service)
+                .await;
+            assert_eq!(res.status_code, Some(StatusCode::BAD_REQUEST), "{}", query);
+        }
+
+        // Purging everything empties the cache
+        let mut res = TestClient::delete(format!("{}?all=true", admin_url))
+            .add_header(header::AUTHORIZATION, "Bearer secret", true)
+            .send(&service)
+            .await;
+        let purged: serde_json::Value = res.take_json().await.unwrap();
+        assert_eq!(purged["purged"], 2);
+
+        let mut res = TestClient::get(admin_url)
+            .add_header(header::AUTHORIZATION, "Bearer secret", true)
+            .send(&service)
+            .await;
+        let listing: serde_json::Value = res.take_json().await.unwrap();
+        assert_eq!(listing["count"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_servers_serves_merged_snapshot() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/get_server_list.php"))
+            .and(query_param("start", "0"))
+            .and(query_param("names", "1"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                "<result><server><name>EU 1</name><address>1.2.3.4</address>\
+                 <port>1234</port><current_players>3</current_players>\
+                 <player>Alice</player></server>\
+                 <server><name>Broken</name><port>1</port></server></result>",
+            ))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let service = test_service(
+            vec![format!("{}/get_server_list.php", mock_server.uri())],
+            vec![],
+        );
+        let url = "http://127.0.0.1:5800/api/servers";
+
+        let mut res = TestClient::get(url).send(&service).await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+        let etag = header_value(&res, header::ETAG);
+        let body: serde_json::Value = res.take_json().await.unwrap();
+        assert_eq!(body["snapshot_id"].as_str().unwrap().len(), 32);
+        assert!(body["fetched_at"].as_u64().unwrap() > 0);
+        assert_eq!(body["pages"], 1);
+        assert_eq!(body["total"], 1);
+        assert_eq!(body["count"], 1);
+        assert!(body["next_cursor"].is_null());
+        assert_eq!(body["servers"][0]["name"], "EU 1");
+        assert_eq!(body["servers"][0]["port"], 1234);
+        assert_eq!(body["servers"][0]["players"][0], "Alice");
+        assert_eq!(body["skipped"][0]["reason"], "missing address");
+
+        // The snapshot is reused, so the client's copy is still current
+        let res = TestClient::get(url)
+            .add_header(header::IF_NONE_MATCH, etag, true)
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::NOT_MODIFIED));
+    }
+
+    #[tokio::test]
+    async fn test_servers_reports_upstream_errors_as_json() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/get_server_list.php"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("Database error"))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/missing.php"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&mock_server)
+            .await;
+
+        let service = test_service(
+            vec![format!("{}/get_server_list.php", mock_server.uri())],
+            vec![],
+        );
+        let mut res = TestClient::get("http://127.0.0.1:5800/api/servers")
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::BAD_GATEWAY));
+        let body: serde_json::Value = res.take_json().await.unwrap();
+        assert!(body["error"].as_str().unwrap().contains("parse"));
+
+        let service = test_service(vec![format!("{}/missing.php", mock_server.uri())], vec![]);
+        let mut res = TestClient::get("http://127.0.0.1:5800/api/servers")
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::BAD_GATEWAY));
+        let body: serde_json::Value = res.take_json().await.unwrap();
+        assert!(body["error"].as_str().unwrap().contains("status 404"));
+    }
+
+    #[tokio::test]
+    async fn test_servers_filters_sorts_and_pages() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/get_server_list.php"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                "<result>\
+                 <server><name>A</name><address>1.1.1.1</address><port>1</port>\
+                 <mode>COOP</mode><current_players>4</current_players></server>\
+                 <server><name>B</name><address>1.1.1.1</address><port>2</port>\
+                 <mode>PvP</mode><current_players>9</current_players></server>\
+                 <server><name>C</name><address>1.1.1.1</address><port>3</port>\
+                 <mode>COOP</mode><current_players>7</current_players></server>\
+                 </result>",
+            ))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let service = test_service(
+            vec![format!("{}/get_server_list.php", mock_server.uri())],
+            vec![],
+        );
+        let url = "http://127.0.0.1:5800/api/servers?mode=coop&limit=1";
+
+        let mut res = TestClient::get(url).send(&service).await;
+        let body: serde_json::Value = res.take_json().await.unwrap();
+        assert_eq!(body["total"], 2);
+        assert_eq!(body["servers"][0]["name"], "C");
+        let cursor = body["next_cursor"].as_str().unwrap().to_string();
+
+        // Further pages come from the same snapshot, without upstream requests
+        let mut res = TestClient::get(format!("{}&cursor={}", url, cursor))
+            .send(&service)
+            .await;
+        let body: serde_json::Value = res.take_json().await.unwrap();
+        assert_eq!(body["servers"][0]["name"], "A");
+        assert!(body["next_cursor"].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_servers_search_expression() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/get_server_list.php"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                "<result>\
+                 <server><name>EU 1</name><address>1.1.1.1</address><port>1</port>\
+                 <mode>COOP</mode><current_players>12</current_players></server>\
+                 <server><name>EU test</name><address>1.1.1.1</address><port>2</port>\
+                 <mode>COOP</mode><current_players>15</current_players></server>\
+                 <server><name>US 1</name><address>1.1.1.1</address><port>3</port>\
+                 <mode>PvP</mode><current_players>20</current_players></server>\
+                 </result>",
+            ))
+            .mount(&mock_server)
+            .await;
+
+        let service = test_service(
+            vec![format!("{}/get_server_list.php", mock_server.uri())],
+            vec![],
+        );
+        let mut res = TestClient::get(
+            "http://127.0.0.1:5800/api/servers?q=mode%3Acoop%20players%3E%3D10%20-name%3Atest",
+        )
+        .send(&service)
+        .await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+        let body: serde_json::Value = res.take_json().await.unwrap();
+        assert_eq!(body["total"], 1);
+        assert_eq!(body["servers"][0]["name"], "EU 1");
+    }
+
+    #[tokio::test]
+    async fn test_servers_rejects_invalid_query() {
+        let service = test_service(vec![], vec![]);
+
+        let mut res = TestClient::get("http://127.0.0.1:5800/api/servers?sort=ping")
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::BAD_REQUEST));
+        let body: serde_json::Value = res.take_json().await.unwrap();
+        assert!(body["error"].as_str().unwrap().contains("invalid sort"));
+    }
+
+    #[tokio::test]
+    async fn test_players_online_finds_servers_of_a_player() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/get_server_list.php"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                "<result>\
+                 <server><name>EU 1</name><address>1.1.1.1</address><port>1</port>\
+                 <player>ALICE</player><player>BOB</player></server>\
+                 <server><name>US 1</name><address>1.1.1.1</address><port>2</port>\
+                 <player>ALICEINCHAINS</player></server>\
+                 <server><name>Empty</name><address>1.1.1.1</address><port>3</port></server>\
+                 </result>",
+            ))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let service = test_service(
+            vec![format!("{}/get_server_list.php", mock_server.uri())],
+            vec![],
+        );
+        let url = "http://127.0.0.1:5800/api/players/online?name=alice";
+
+        let mut res = TestClient::get(url).send(&service).await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+        let etag = header_value(&res, header::ETAG);
+        let body: serde_json::Value = res.take_json().await.unwrap();
+        assert_eq!(body["name"], "alice");
+        assert_eq!(body["snapshot_id"].as_str().unwrap().len(), 32);
+        assert_eq!(body["count"], 2);
+        assert_eq!(body["servers"][0]["name"], "EU 1");
+        assert_eq!(
+            body["servers"][0]["matched_players"][0]["match"],
+            "case_insensitive"
+        );
+        assert_eq!(body["servers"][1]["name"], "US 1");
+        assert_eq!(body["servers"][1]["matched_players"][0]["match"], "prefix");
+
+        // Answered from the same snapshot
+        let res = TestClient::get(url)
+            .add_header(header::IF_NONE_MATCH, etag, true)
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::NOT_MODIFIED));
+
+        let mut res = TestClient::get("http://127.0.0.1:5800/api/players/online?name=carol")
+            .send(&service)
+            .await;
+        let body: serde_json::Value = res.take_json().await.unwrap();
+        assert_eq!(body["count"], 0);
+        assert_eq!(body["servers"], serde_json::json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_players_online_requires_a_name() {
+        let service = test_service(vec![], vec![]);
+
+        let mut res = TestClient::get("http://127.0.0.1:5800/api/players/online")
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::BAD_REQUEST));
+        let body: serde_json::Value = res.take_json().await.unwrap();
+        assert_eq!(body["error"], "name is required");
+    }
+
+    #[tokio::test]
+    async fn test_servers_reports_search_syntax_errors() {
+        let service = test_service(vec![], vec![]);
+
+        let mut res =
+            TestClient::get("http://127.0.0.1:5800/api/servers?q=mode%3Acoop%20colour%3Ared")
+                .send(&service)
+                .await;
+        assert_eq!(res.status_code, Some(StatusCode::BAD_REQUEST));
+        let body: serde_json::Value = res.take_json().await.unwrap();
+        assert_eq!(body["error"], "unknown field 'colour'");
+        assert_eq!(body["position"], 10);
+        assert!(body["expected"].as_str().unwrap().contains("players"));
+    }
+}
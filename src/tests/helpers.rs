@@ -0,0 +1,98 @@
+use crate::{
+    ApiCache, CacheBackend, CachePolicy, Config, GameServer, ProxyRoute, SERVER_LIST_PARAMS,
+    UpstreamClient, UpstreamConfig,
+};
+use wiremock::MockServer;
+
+/// Cache whose upstream client does not retry, for tests that count
+/// upstream requests.
+pub fn cache_without_retries(cache_expiry_secs: u64) -> ApiCache {
+    let client = UpstreamClient::new(UpstreamConfig {
+        max_retries: 0,
+        ..UpstreamConfig::default()
+    })
+    .unwrap();
+    ApiCache::new(cache_expiry_secs).with_client(client)
+}
+
+/// Default configuration, built without reading the environment so tests
+/// that set variables cannot change it.
+pub fn test_config() -> Config {
+    Config {
+        port: "5800".to_string(),
+        host: "127.0.0.1".to_string(),
+        cache_duration_secs: 3,
+        cache_stale_secs: 0,
+        cache_stale_if_error_secs: 300,
+        cache_max_entries: 1000,
+        cache_max_bytes: 64 * 1024 * 1024,
+        cache_sweep_interval_secs: 60,
+        cache_compression: true,
+        cache_backend: CacheBackend::Memory,
+        cache_redis_url: "redis://127.0.0.1:6379".to_string(),
+        cache_redis_prefix: "rwrs:".to_string(),
+        cache_disk_path: "cache".to_string(),
+        cache_snapshot_path: None,
+        cache_snapshot_interval_secs: 300,
+        server_list_cache_secs: 3,
+        player_list_cache_secs: 3,
+        version_cache_secs: 300,
+        server_list_negative_cache_secs: 1,
+        player_list_negative_cache_secs: 1,
+        version_negative_cache_secs: 60,
+        adaptive_ttl_min_secs: 1,
+        adaptive_ttl_max_secs: 0,
+        server_list_upstreams: vec![],
+        server_list_page_size: 100,
+        server_list_max_pages: 20,
+        player_list_upstreams: vec![],
+        poller_interval_secs: 0,
+        poller_queries: vec![],
+        poller_learned_queries: 5,
+        upstream_connect_timeout_secs: 5,
+        upstream_read_timeout_secs: 10,
+        upstream_timeout_secs: 10,
+        upstream_user_agent: "rwrs-server-test".to_string(),
+        upstream_max_retries: 2,
+        upstream_retry_backoff_ms: 200,
+        upstream_max_response_bytes: 8 * 1024 * 1024,
+        upstream_rate_limit: 10.0,
+        upstream_rate_limit_burst: 20,
+        upstream_max_concurrent: 8,
+        upstream_rate_limit_wait_ms: 1000,
+        breaker_failure_threshold: 5,
+        breaker_cool_down_secs: 30,
+        maps_config_path: "maps.json".to_string(),
+        admin_token: None,
+        android_repo_url: None,
+        web_repo_url: None,
+    }
+}
+
+/// Server list route whose only upstream is `mock_server`.
+pub fn test_route(mock_server: &MockServer, policy: CachePolicy) -> ProxyRoute {
+    ProxyRoute {
+        name: "server_list",
+        upstreams: vec![format!("{}/get_server_list.php", mock_server.uri())],
+        params: SERVER_LIST_PARAMS,
+        policy,
+    }
+}
+
+/// Empty dedicated co-op server on `10.0.0.1:port`. Tests set the fields
+/// they depend on with struct update syntax.
+pub fn game_server(name: &str, port: u16) -> GameServer {
+    GameServer {
+        name: name.to_string(),
+        address: "10.0.0.1".to_string(),
+        port,
+        map_id: "media/packages/vanilla/maps/map9".to_string(),
+        map_name: "Keepsake Bay".to_string(),
+        mode: "COOP".to_string(),
+        realm: "official_invasion".to_string(),
+        version: "1.98".to_string(),
+        dedicated: true,
+        max_players: 32,
+        ..GameServer::default()
+    }
+}
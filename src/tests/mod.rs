@@ -1,3 +1,20 @@
+pub mod adaptive_ttl_tests;
 pub mod basic_tests;
+pub mod body_tests;
 pub mod cache_tests;
+pub mod circuit_breaker_tests;
+pub mod compression_tests;
+pub mod handler_tests;
+pub mod helpers;
 pub mod integration_tests;
+pub mod player_search_tests;
+pub mod poller_tests;
+pub mod query_tests;
+pub mod rate_limit_tests;
+pub mod search_tests;
+pub mod server_filter_tests;
+pub mod server_list_tests;
+pub mod server_snapshot_tests;
+pub mod snapshot_tests;
+pub mod store_tests;
+pub mod upstream_tests;
@@ -0,0 +1,71 @@
+#[cfg(test)]
+mod tests {
+    use crate::tests::helpers::game_server;
+    use crate::{GameServer, PlayerMatchKind, find_online_players, parse_player_name};
+
+    fn server(name: &str, port: u16, players: &[&str]) -> GameServer {
+        GameServer {
+            current_players: players.len() as u32,
+            players: players.iter().map(|player| player.to_string()).collect(),
+            ..game_server(name, port)
+        }
+    }
+
+    fn servers() -> Vec<GameServer> {
+        vec![
+            server("A", 1, &["ALICEBOT", "BOB"]),
+            server("B", 2, &["alice", "CAROL"]),
+            server("C", 3, &["Alice"]),
+            server("D", 4, &["DAVE"]),
+        ]
+    }
+
+    #[test]
+    fn test_exact_case_insensitive_and_prefix_matches() {
+        let servers = servers();
+        let found = find_online_players(&servers, "Alice");
+        let names: Vec<&str> = found.iter().map(|s| s.server.name.as_str()).collect();
+        // Best match first
+        assert_eq!(names, ["C", "B", "A"]);
+        assert_eq!(found[0].matched_players[0].kind, PlayerMatchKind::Exact);
+        assert_eq!(
+            found[1].matched_players[0].kind,
+            PlayerMatchKind::CaseInsensitive
+        );
+        assert_eq!(found[2].matched_players[0].name, "ALICEBOT");
+        assert_eq!(found[2].matched_players[0].kind, PlayerMatchKind::Prefix);
+    }
+
+    #[test]
+    fn test_only_matching_players_are_listed() {
+        let servers = servers();
+        let found = find_online_players(&servers, "bo");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].matched_players.len(), 1);
+        assert_eq!(found[0].matched_players[0].name, "BOB");
+
+        assert!(find_online_players(&servers, "eve").is_empty());
+        // Names are matched from the start only
+        assert!(find_online_players(&servers, "ave").is_empty());
+    }
+
+    #[test]
+    fn test_serializes_server_with_matches() {
+        let servers = servers();
+        let found = find_online_players(&servers, "dave");
+        let json = serde_json::to_value(&found).unwrap();
+        assert_eq!(json[0]["name"], "D");
+        assert_eq!(json[0]["port"], 4);
+        assert_eq!(json[0]["matched_players"][0]["name"], "DAVE");
+        assert_eq!(json[0]["matched_players"][0]["match"], "case_insensitive");
+    }
+
+    #[test]
+    fn test_parse_player_name() {
+        assert_eq!(parse_player_name(Some(" alice ")), Ok("alice"));
+        assert!(parse_player_name(None).unwrap_err().contains("required"));
+        assert!(parse_player_name(Some("  ")).is_err());
+        assert!(parse_player_name(Some(&"a".repeat(64))).is_ok());
+        assert!(parse_player_name(Some(&"a".repeat(65))).is_err());
+    }
+}
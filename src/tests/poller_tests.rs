@@ -0,0 +1,159 @@
+#[cfg(test)]
+mod tests {
+    use crate::tests::helpers::test_route;
+    use crate::{ApiCache, CachePolicy, CachePoller, CacheStatus, PollerConfig, PopularQueries};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::sync::watch;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{method, path, query_param},
+    };
+
+    fn test_poller(
+        mock_server: &MockServer,
+        cache: Arc<ApiCache>,
+        queries: &[&str],
+        popular: Arc<PopularQueries>,
+    ) -> CachePoller {
+        let config = PollerConfig {
+            interval: Duration::from_millis(50),
+            queries: queries.iter().map(|query| query.to_string()).collect(),
+            learned_queries: 2,
+        };
+        CachePoller::new(
+            cache,
+            test_route(mock_server, CachePolicy::new(10, 1)),
+            config,
+            popular,
+        )
+    }
+
+    #[test]
+    fn test_popular_queries_ranking() {
+        let popular = PopularQueries::new();
+        for _ in 0..3 {
+            popular.record("start=0");
+        }
+        popular.record("start=100");
+        popular.record("start=200");
+        popular.record("start=200");
+
+        assert_eq!(popular.top(2), vec!["start=0", "start=200"]);
+        assert_eq!(popular.top(10).len(), 3);
+    }
+
+    #[test]
+    fn test_popular_queries_decay_forgets_rare_queries() {
+        let popular = PopularQueries::new();
+        popular.record("start=0");
+        popular.record("start=0");
+        popular.record("start=100");
+
+        popular.decay();
+        assert_eq!(popular.top(10), vec!["start=0"]);
+
+        popular.decay();
+        assert!(popular.top(10).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_poller_queries_are_canonical_and_unique() {
+        let mock_server = MockServer::start().await;
+        let popular = Arc::new(PopularQueries::new());
+        popular.record("size=100&start=0");
+        popular.record("start=100");
+
+        let poller = test_poller(
+            &mock_server,
+            Arc::new(ApiCache::new(10)),
+            &["start=0&size=100&_=1"],
+            popular,
+        );
+
+        assert_eq!(poller.queries(), vec!["size=100&start=0", "start=100"]);
+    }
+
+    #[tokio::test]
+    async fn test_poll_once_warms_cache() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/get_server_list.php"))
+            .and(query_param("start", "0"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("page 0"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/get_server_list.php"))
+            .and(query_param("start", "100"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("page 1"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let cache = Arc::new(ApiCache::new(10));
+        let popular = Arc::new(PopularQueries::new());
+        popular.record("start=100");
+        let poller = test_poller(&mock_server, cache.clone(), &["start=0"], popular);
+
+        assert_eq!(poller.poll_once().await, 2);
+
+        // Both queries are now served from the cache without another request
+        let route = test_route(&mock_server, CachePolicy::new(10, 1));
+        let lookup = cache.lookup_route(&route, "start=0").await.unwrap();
+        assert_eq!(lookup.status, CacheStatus::Hit);
+        assert_eq!(lookup.data, "page 0");
+        let lookup = cache.lookup_route(&route, "start=100").await.unwrap();
+        assert_eq!(lookup.status, CacheStatus::Hit);
+        assert_eq!(lookup.data, "page 1");
+    }
+
+    #[tokio::test]
+    async fn test_poll_once_does_not_count_errors() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/get_server_list.php"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&mock_server)
+            .await;
+
+        let poller = test_poller(
+            &mock_server,
+            Arc::new(ApiCache::new(10)),
+            &["start=0"],
+            Arc::new(PopularQueries::new()),
+        );
+
+        assert_eq!(poller.poll_once().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_spawned_poller_stops_on_shutdown() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/get_server_list.php"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("page 0"))
+            .mount(&mock_server)
+            .await;
+
+        let cache = Arc::new(ApiCache::new(10));
+        let poller = test_poller(
+            &mock_server,
+            cache.clone(),
+            &["start=0"],
+            Arc::new(PopularQueries::new()),
+        );
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let handle = poller.spawn(shutdown_rx);
+
+        tokio::time::sleep(Duration::from_millis(120)).await;
+        assert_eq!(cache.len().await, 1);
+
+        shutdown_tx.send(true).unwrap();
+        tokio::time::timeout(Duration::from_secs(1), handle)
+            .await
+            .expect("poller did not stop")
+            .unwrap();
+    }
+}
@@ -0,0 +1,69 @@
+#[cfg(test)]
+mod tests {
+    use crate::{PLAYER_LIST_PARAMS, SERVER_LIST_PARAMS, build_url, canonical_query};
+
+    #[test]
+    fn test_param_order_does_not_matter() {
+        let a = canonical_query("start=0&size=100", SERVER_LIST_PARAMS);
+        let b = canonical_query("size=100&start=0", SERVER_LIST_PARAMS);
+        assert_eq!(a, b);
+        assert_eq!(a, "size=100&start=0");
+    }
+
+    #[test]
+    fn test_unknown_and_empty_params_are_dropped() {
+        let key = canonical_query("start=0&_=1699999999&size=&foo=bar", SERVER_LIST_PARAMS);
+        assert_eq!(key, "start=0");
+    }
+
+    #[test]
+    fn test_param_names_are_case_sensitive() {
+        // The upstream ignores `START`, so it must not share a key with `start`
+        let key = canonical_query("START=100&Size=50&size=10", SERVER_LIST_PARAMS);
+        assert_eq!(key, "size=10");
+    }
+
+    #[test]
+    fn test_numbers_are_normalised() {
+        let key = canonical_query("start=007&size=+100&names=abc", SERVER_LIST_PARAMS);
+        assert_eq!(key, "size=100&start=7");
+    }
+
+    #[test]
+    fn test_last_repeated_param_wins() {
+        let key = canonical_query("start=0&start=100", SERVER_LIST_PARAMS);
+        assert_eq!(key, "start=100");
+
+        // An empty repeat clears the earlier value
+        let key = canonical_query("start=100&start=", SERVER_LIST_PARAMS);
+        assert_eq!(key, "");
+    }
+
+    #[test]
+    fn test_player_list_values() {
+        let key = canonical_query(
+            "sort=Kills&order=DESC&search=Player%20One&db=Invasion",
+            PLAYER_LIST_PARAMS,
+        );
+        // Enumerated values are lowercased, player names are kept as-is
+        assert_eq!(key, "db=invasion&order=desc&search=Player+One&sort=kills");
+    }
+
+    #[test]
+    fn test_empty_query() {
+        assert_eq!(canonical_query("", SERVER_LIST_PARAMS), "");
+        assert_eq!(canonical_query("&&=", SERVER_LIST_PARAMS), "");
+    }
+
+    #[test]
+    fn test_build_url() {
+        assert_eq!(
+            build_url("http://host/list.php", ""),
+            "http://host/list.php"
+        );
+        assert_eq!(
+            build_url("http://host/list.php", "start=0"),
+            "http://host/list.php?start=0"
+        );
+    }
+}
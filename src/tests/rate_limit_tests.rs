@@ -0,0 +1,107 @@
+#[cfg(test)]
+mod tests {
+    use crate::{RateLimitConfig, RateLimiter};
+    use std::time::{Duration, Instant};
+
+    #[tokio::test]
+    async fn test_burst_then_reject_without_wait() {
+        let limiter = RateLimiter::new(RateLimitConfig::new(1.0, 3, 0, 0));
+
+        for _ in 0..3 {
+            assert!(limiter.acquire().await.is_ok());
+        }
+        let err = limiter.acquire().await.unwrap_err();
+        assert!(err.contains("rate limit"));
+        assert_eq!(limiter.status().rejected, 1);
+    }
+
+    #[tokio::test]
+    async fn test_waits_for_refill_within_max_wait() {
+        let limiter = RateLimiter::new(RateLimitConfig::new(20.0, 1, 0, 500));
+
+        assert!(limiter.acquire().await.is_ok());
+        let started = Instant::now();
+        assert!(limiter.acquire().await.is_ok());
+        // One token refills every 50 ms
+        assert!(started.elapsed() >= Duration::from_millis(40));
+        assert_eq!(limiter.status().rejected, 0);
+    }
+
+    #[tokio::test]
+    async fn test_rejects_when_queue_exceeds_max_wait() {
+        let limiter = RateLimiter::new(RateLimitConfig::new(10.0, 1, 0, 150));
+
+        // The second request waits 100 ms, the third would wait 200 ms
+        assert!(limiter.acquire().await.is_ok());
+        let waiting = tokio::spawn({
+            let limiter = limiter.clone();
+            async move { limiter.acquire().await.is_ok() }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(limiter.acquire().await.is_err());
+        assert!(waiting.await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_cap() {
+        let limiter = RateLimiter::new(RateLimitConfig::new(0.0, 0, 1, 50));
+
+        let permit = limiter.acquire().await.unwrap();
+        assert_eq!(limiter.status().in_flight, 1);
+        let err = limiter.acquire().await.unwrap_err();
+        assert!(err.contains("in flight"));
+
+        drop(permit);
+        assert_eq!(limiter.status().in_flight, 0);
+        assert!(limiter.acquire().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_waits_for_free_slot() {
+        let limiter = RateLimiter::new(RateLimitConfig::new(0.0, 0, 1, 500));
+
+        let permit = limiter.acquire().await.unwrap();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            drop(permit);
+        });
+        assert!(limiter.acquire().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_token_refunded_when_no_slot_frees_up() {
+        let limiter = RateLimiter::new(RateLimitConfig::new(0.001, 2, 1, 50));
+
+        let permit = limiter.acquire().await.unwrap();
+        assert!(limiter.acquire().await.unwrap_err().contains("in flight"));
+
+        // The rejected request did not use up the second token
+        drop(permit);
+        assert!(limiter.acquire().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_tiny_rate_rejects_instead_of_overflowing() {
+        let limiter = RateLimiter::new(RateLimitConfig::new(1e-300, 1, 0, 1000));
+
+        assert!(limiter.acquire().await.is_ok());
+        assert!(limiter.acquire().await.unwrap_err().contains("rate limit"));
+    }
+
+    #[tokio::test]
+    async fn test_disabled_limiter_never_rejects() {
+        let limiter = RateLimiter::new(RateLimitConfig::disabled());
+
+        let permits = acquire_many(&limiter, 100).await;
+        assert_eq!(permits.len(), 100);
+        assert_eq!(limiter.status().rejected, 0);
+    }
+
+    async fn acquire_many(limiter: &RateLimiter, count: usize) -> Vec<crate::UpstreamPermit> {
+        let mut permits = Vec::new();
+        for _ in 0..count {
+            permits.push(limiter.acquire().await.unwrap());
+        }
+        permits
+    }
+}
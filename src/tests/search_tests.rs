@@ -0,0 +1,126 @@
+#[cfg(test)]
+mod tests {
+    use crate::tests::helpers::game_server;
+    use crate::{GameServer, SearchQuery};
+
+    fn server() -> GameServer {
+        GameServer {
+            bots: 2,
+            current_players: 12,
+            comment: "Invasion campaign, be nice".to_string(),
+            players: vec!["Alice".to_string(), "Bob".to_string()],
+            ..game_server("Official EU 1", 1234)
+        }
+    }
+
+    fn matches(query: &str) -> bool {
+        SearchQuery::parse(query).unwrap().matches(&server())
+    }
+
+    #[test]
+    fn test_example_expression() {
+        assert!(matches(
+            "mode:coop players>=10 map:map9 -name:test version:1.9*"
+        ));
+        assert!(!matches("mode:coop players>=10 map:map9 -name:official"));
+    }
+
+    #[test]
+    fn test_empty_expression_matches_everything() {
+        assert!(matches(""));
+        assert!(matches("   "));
+    }
+
+    #[test]
+    fn test_text_fields() {
+        assert!(matches("name:eu"));
+        assert!(matches("eu official"));
+        assert!(!matches("eu us"));
+        assert!(matches("map:\"keepsake bay\""));
+        assert!(matches("map:media/packages/vanilla/maps/map9"));
+        assert!(!matches("map:map"));
+        assert!(matches("mode=COOP"));
+        assert!(!matches("mode:co"));
+        assert!(matches("realm:official_*"));
+        assert!(matches("comment:campaign"));
+        assert!(matches("player:alice"));
+        assert!(!matches("player:ali"));
+        assert!(matches("player:ali*"));
+        assert!(matches("address:10.0.0.*"));
+        assert!(!matches("name:\"official \\\"eu\\\"\""));
+    }
+
+    #[test]
+    fn test_number_fields() {
+        assert!(matches("players=12"));
+        assert!(matches("players:12"));
+        assert!(matches("players>11 players<13"));
+        assert!(!matches("players>12"));
+        assert!(matches("players<=12 players>=12"));
+        assert!(matches("max_players=32"));
+        assert!(matches("free=20"));
+        assert!(matches("bots>0"));
+        assert!(!matches("-bots>0"));
+    }
+
+    #[test]
+    fn test_dedicated() {
+        assert!(matches("dedicated:true"));
+        assert!(matches("dedicated:yes"));
+        assert!(!matches("dedicated:0"));
+    }
+
+    #[test]
+    fn test_glob_wildcards() {
+        assert!(matches("version:1.9*"));
+        assert!(matches("version:*.98"));
+        assert!(matches("version:1*9*"));
+        assert!(!matches("version:2.*"));
+        assert!(matches("name:off*eu"));
+    }
+
+    fn error(query: &str) -> (String, Option<usize>, Option<String>) {
+        let err = SearchQuery::parse(query).unwrap_err();
+        (err.error, err.position, err.expected)
+    }
+
+    #[test]
+    fn test_error_positions() {
+        let (message, position, expected) = error("mode:coop colour:red");
+        assert_eq!(message, "unknown field 'colour'");
+        assert_eq!(position, Some(10));
+        assert!(expected.unwrap().contains("dedicated"));
+
+        let (message, position, _) = error("players>=ten");
+        assert_eq!(message, "invalid number 'ten' for 'players'");
+        assert_eq!(position, Some(9));
+
+        let (message, position, expected) = error("map:");
+        assert_eq!(message, "expected a value");
+        assert_eq!(position, Some(4));
+        assert_eq!(expected.as_deref(), Some("a word or a quoted string"));
+
+        let (message, position, _) = error("name:\"open");
+        assert!(message.starts_with("unterminated quote"));
+        assert_eq!(position, Some(10));
+
+        let (_, position, _) = error("mode>coop");
+        assert_eq!(position, Some(4));
+        let (_, position, _) = error("dedicated:maybe");
+        assert_eq!(position, Some(10));
+        let (_, position, _) = error("coop - x");
+        assert_eq!(position, Some(6));
+        let (_, position, _) = error(":coop");
+        assert_eq!(position, Some(0));
+    }
+
+    #[test]
+    fn test_error_display_and_length_limit() {
+        let err = SearchQuery::parse("colour:red").unwrap_err();
+        assert_eq!(err.to_string(), "unknown field 'colour' at position 0");
+
+        let err = SearchQuery::parse(&"a".repeat(513)).unwrap_err();
+        assert!(err.position.is_none());
+        assert!(err.error.contains("512"));
+    }
+}
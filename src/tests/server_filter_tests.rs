@@ -0,0 +1,167 @@
+#[cfg(test)]
+mod tests {
+    use crate::tests::helpers::game_server;
+    use crate::{GameServer, ServerQuery, ServerSort};
+
+    fn server(name: &str, port: u16, players: u32, max_players: u32) -> GameServer {
+        GameServer {
+            current_players: players,
+            max_players,
+            ..game_server(name, port)
+        }
+    }
+
+    fn servers() -> Vec<GameServer> {
+        let mut home = server("Home game", 4, 0, 8);
+        home.dedicated = false;
+        home.mode = "PvP".to_string();
+        home.map_id = "media/packages/pacific/maps/island1".to_string();
+        home.map_name = "Island".to_string();
+        home.version = "1.97".to_string();
+        vec![
+            server("Official EU 1", 1, 12, 32),
+            server("Official US 1", 2, 32, 32),
+            server("Test server", 3, 5, 16),
+            home,
+        ]
+    }
+
+    fn names(query: &str) -> Vec<String> {
+        let servers = servers();
+        let query = ServerQuery::parse(query).unwrap();
+        query
+            .apply(&servers)
+            .servers
+            .iter()
+            .map(|server| server.name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_default_sorts_by_players_descending() {
+        assert_eq!(
+            names(""),
+            ["Official US 1", "Official EU 1", "Test server", "Home game"]
+        );
+    }
+
+    #[test]
+    fn test_filters() {
+        assert_eq!(names("mode=pvp"), ["Home game"]);
+        assert_eq!(names("map=island1"), ["Home game"]);
+        assert_eq!(
+            names("map=media/packages/vanilla/maps/map9&sort=name&order=asc"),
+            ["Official EU 1", "Official US 1", "Test server"]
+        );
+        assert_eq!(names("version=1.97"), ["Home game"]);
+        assert_eq!(names("dedicated=true").len(), 3);
+        assert_eq!(
+            names("free_slots=1"),
+            ["Official EU 1", "Test server", "Home game"]
+        );
+        assert_eq!(
+            names("min_players=5&max_players=12"),
+            ["Official EU 1", "Test server"]
+        );
+        assert_eq!(names("not_empty=true").len(), 3);
+        assert_eq!(names("name=OFFICIAL"), ["Official US 1", "Official EU 1"]);
+        assert_eq!(names("name_regex=^Official (EU|DE)"), ["Official EU 1"]);
+    }
+
+    #[test]
+    fn test_sort_by_name_and_map() {
+        assert_eq!(
+            names("sort=name&order=asc"),
+            ["Home game", "Official EU 1", "Official US 1", "Test server"]
+        );
+        assert_eq!(names("sort=map&order=desc")[3], "Home game");
+    }
+
+    #[test]
+    fn test_cursor_pagination() {
+        let servers = servers();
+        let query = ServerQuery::parse("limit=3").unwrap();
+        let first = query.apply(&servers);
+        assert_eq!(first.total, 4);
+        assert_eq!(first.servers.len(), 3);
+        let cursor = first.next_cursor.unwrap();
+
+        let query = ServerQuery::parse(&format!("limit=3&cursor={}", cursor)).unwrap();
+        let second = query.apply(&servers);
+        assert_eq!(second.servers.len(), 1);
+        assert_eq!(second.servers[0].name, "Home game");
+        assert!(second.next_cursor.is_none());
+    }
+
+    #[test]
+    fn test_cursor_survives_snapshot_changes() {
+        let mut servers = servers();
+        let query = ServerQuery::parse("sort=name&order=asc&limit=2").unwrap();
+        let cursor = query.apply(&servers).next_cursor.unwrap();
+
+        // A server listed before the cursor disappears
+        servers.retain(|server| server.name != "Home game");
+        let query =
+            ServerQuery::parse(&format!("sort=name&order=asc&limit=2&cursor={}", cursor)).unwrap();
+        let page = query.apply(&servers);
+        let names: Vec<&str> = page.servers.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["Official US 1", "Test server"]);
+    }
+
+    #[test]
+    fn test_cursor_must_match_sort() {
+        let servers = servers();
+        let query = ServerQuery::parse("limit=1").unwrap();
+        let cursor = query.apply(&servers).next_cursor.unwrap();
+
+        let err = ServerQuery::parse(&format!("sort=name&cursor={}", cursor)).unwrap_err();
+        assert!(err.error.contains("different sort order"));
+        assert_eq!(
+            ServerQuery::parse("cursor=garbage").unwrap_err().error,
+            "invalid cursor"
+        );
+    }
+
+    #[test]
+    fn test_invalid_parameters() {
+        assert!(
+            ServerQuery::parse("sort=ping")
+                .unwrap_err()
+                .error
+                .contains("invalid sort")
+        );
+        assert!(
+            ServerQuery::parse("order=up")
+                .unwrap_err()
+                .error
+                .contains("invalid order")
+        );
+        assert!(
+            ServerQuery::parse("min_players=-1")
+                .unwrap_err()
+                .error
+                .contains("min_players")
+        );
+        assert!(
+            ServerQuery::parse("dedicated=yes")
+                .unwrap_err()
+                .error
+                .contains("dedicated")
+        );
+        assert!(ServerQuery::parse("limit=0").is_err());
+        assert!(ServerQuery::parse("limit=501").is_err());
+        assert!(
+            ServerQuery::parse("name_regex=(")
+                .unwrap_err()
+                .error
+                .contains("name_regex")
+        );
+        assert!(ServerQuery::parse(&format!("name_regex={}", "a".repeat(300))).is_err());
+    }
+
+    #[test]
+    fn test_unknown_parameters_are_ignored() {
+        let query = ServerQuery::parse("_=1700000000&sort=Name").unwrap();
+        assert_eq!(query.sort, ServerSort::Name);
+    }
+}
@@ -0,0 +1,121 @@
+#[cfg(test)]
+mod tests {
+    use crate::{GameServer, parse_server_list};
+
+    const SERVER_LIST: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<result>
+  <server>
+    <name>Official Invasion EU 1</name>
+    <address>185.28.101.12</address>
+    <port>1234</port>
+    <map_id>media/packages/vanilla/maps/map9</map_id>
+    <map_name>Keepsake Bay</map_name>
+    <bots>42</bots>
+    <current_players>2</current_players>
+    <timeStamp>1700000000</timeStamp>
+    <version>1.98</version>
+    <dedicated>1</dedicated>
+    <mod>0</mod>
+    <player>Alice</player>
+    <player>Bob &amp; Co</player>
+    <comment>Invasion &lt;EU&gt;</comment>
+    <url>https://example.com</url>
+    <max_players>32</max_players>
+    <mode>COOP</mode>
+    <realm>official_invasion</realm>
+  </server>
+  <server>
+    <name>Broken port</name>
+    <address>10.0.0.1</address>
+    <port>not-a-port</port>
+  </server>
+  <server>
+    <name>Home game</name>
+    <address>10.0.0.2</address>
+    <port>1240</port>
+    <dedicated>0</dedicated>
+    <current_players>0</current_players>
+    <max_players>8</max_players>
+  </server>
+  <server>
+    <name>No address</name>
+    <port>1234</port>
+  </server>
+</result>"#;
+
+    #[test]
+    fn test_parses_all_fields() {
+        let list = parse_server_list(SERVER_LIST).unwrap();
+
+        assert_eq!(
+            list.servers[0],
+            GameServer {
+                name: "Official Invasion EU 1".to_string(),
+                address: "185.28.101.12".to_string(),
+                port: 1234,
+                map_id: "media/packages/vanilla/maps/map9".to_string(),
+                map_name: "Keepsake Bay".to_string(),
+                mode: "COOP".to_string(),
+                realm: "official_invasion".to_string(),
+                version: "1.98".to_string(),
+                dedicated: true,
+                bots: 42,
+                current_players: 2,
+                max_players: 32,
+                comment: "Invasion <EU>".to_string(),
+                url: "https://example.com".to_string(),
+                players: vec!["Alice".to_string(), "Bob & Co".to_string()],
+            }
+        );
+        assert_eq!(list.servers[0].endpoint(), "185.28.101.12:1234");
+    }
+
+    #[test]
+    fn test_missing_optional_fields_use_defaults() {
+        let list = parse_server_list(SERVER_LIST).unwrap();
+
+        let home = &list.servers[1];
+        assert_eq!(home.name, "Home game");
+        assert!(!home.dedicated);
+        assert_eq!(home.bots, 0);
+        assert_eq!(home.max_players, 8);
+        assert!(home.players.is_empty());
+        assert!(home.map_id.is_empty());
+    }
+
+    #[test]
+    fn test_malformed_servers_are_skipped_and_reported() {
+        let list = parse_server_list(SERVER_LIST).unwrap();
+
+        assert_eq!(list.servers.len(), 2);
+        assert_eq!(list.skipped.len(), 2);
+        assert_eq!(list.skipped[0].index, 1);
+        assert_eq!(list.skipped[0].name.as_deref(), Some("Broken port"));
+        assert!(list.skipped[0].reason.contains("port"));
+        assert_eq!(list.skipped[1].index, 3);
+        assert_eq!(list.skipped[1].reason, "missing address");
+    }
+
+    #[test]
+    fn test_invalid_dedicated_flag_is_malformed() {
+        let xml = "<result><server><address>a</address><port>1</port>\
+                   <dedicated>maybe</dedicated></server></result>";
+        let list = parse_server_list(xml).unwrap();
+
+        assert!(list.servers.is_empty());
+        assert!(list.skipped[0].reason.contains("dedicated"));
+    }
+
+    #[test]
+    fn test_empty_list() {
+        let list = parse_server_list("<result></result>").unwrap();
+        assert!(list.servers.is_empty());
+        assert!(list.skipped.is_empty());
+    }
+
+    #[test]
+    fn test_body_that_is_not_xml_is_an_error() {
+        let err = parse_server_list("Service temporarily unavailable").unwrap_err();
+        assert!(err.contains("Failed to parse server list"));
+    }
+}
@@ -0,0 +1,223 @@
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ApiCache, CachePolicy, ProxyRoute, SERVER_LIST_PARAMS, ServerSnapshotConfig,
+        ServerSnapshots,
+    };
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::time::sleep;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{method, path, query_param},
+    };
+
+    fn server(name: &str, port: u16, players: u32) -> String {
+        format!(
+            "<server><name>{}</name><address>10.0.0.1</address><port>{}</port>\
+             <current_players>{}</current_players></server>",
+            name, port, players
+        )
+    }
+
+    async fn mount_page(mock_server: &MockServer, start: &str, servers: &[String]) {
+        Mock::given(method("GET"))
+            .and(path("/get_server_list.php"))
+            .and(query_param("start", start))
+            .and(query_param("size", "2"))
+            .and(query_param("names", "1"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(format!("<result>{}</result>", servers.concat())),
+            )
+            .mount(mock_server)
+            .await;
+    }
+
+    fn snapshots(mock_server: &MockServer, ttl_secs: u64, max_pages: usize) -> ServerSnapshots {
+        let route = ProxyRoute {
+            name: "server_list",
+            upstreams: vec![format!("{}/get_server_list.php", mock_server.uri())],
+            params: SERVER_LIST_PARAMS,
+            policy: CachePolicy::new(ttl_secs, 0),
+        };
+        let config = ServerSnapshotConfig {
+            page_size: 2,
+            max_pages,
+        };
+        ServerSnapshots::new(Arc::new(ApiCache::new(ttl_secs)), route, config)
+    }
+
+    #[tokio::test]
+    async fn test_merges_pages_until_short_page() {
+        let mock_server = MockServer::start().await;
+        mount_page(&mock_server, "0", &[server("A", 1, 5), server("B", 2, 4)]).await;
+        mount_page(&mock_server, "2", &[server("C", 3, 3), server("D", 4, 2)]).await;
+        mount_page(&mock_server, "4", &[server("E", 5, 1)]).await;
+
+        let snapshot = snapshots(&mock_server, 10, 10).current().await.unwrap();
+        let names: Vec<&str> = snapshot.servers.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["A", "B", "C", "D", "E"]);
+        assert_eq!(snapshot.pages, 3);
+        assert_eq!(snapshot.id.len(), 32);
+    }
+
+    #[tokio::test]
+    async fn test_deduplicates_by_address_and_port() {
+        let mock_server = MockServer::start().await;
+        mount_page(&mock_server, "0", &[server("A", 1, 5), server("B", 2, 4)]).await;
+        // B moved down a page between the two requests
+        mount_page(&mock_server, "2", &[server("B", 2, 4)]).await;
+
+        let snapshot = snapshots(&mock_server, 10, 10).current().await.unwrap();
+        assert_eq!(snapshot.servers.len(), 2);
+        assert_eq!(snapshot.servers[1].endpoint(), "10.0.0.1:2");
+    }
+
+    #[tokio::test]
+    async fn test_stops_at_max_pages() {
+        let mock_server = MockServer::start().await;
+        mount_page(&mock_server, "0", &[server("A", 1, 5), server("B", 2, 4)]).await;
+        mount_page(&mock_server, "2", &[server("C", 3, 3), server("D", 4, 2)]).await;
+
+        let snapshot = snapshots(&mock_server, 10, 1).current().await.unwrap();
+        assert_eq!(snapshot.pages, 1);
+        assert_eq!(snapshot.servers.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_is_shared_until_a_page_expires() {
+        let mock_server = MockServer::start().await;
+        mount_page(&mock_server, "0", &[server("A", 1, 5)]).await;
+
+        let snapshots = snapshots(&mock_server, 1, 10);
+        let first = snapshots.current().await.unwrap();
+        let second = snapshots.current().await.unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert!(!first.remaining_ttl().is_zero());
+
+        sleep(Duration::from_millis(1100)).await;
+        let rebuilt = snapshots.current().await.unwrap();
+        assert!(!Arc::ptr_eq(&first, &rebuilt));
+        // Same servers, same id
+        assert_eq!(first.id, rebuilt.id);
+    }
+
+    #[tokio::test]
+    async fn test_previous_snapshot_served_when_rebuild_fails() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/get_server_list.php"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(format!("<result>{}</result>", server("A", 1, 5))),
+            )
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/get_server_list.php"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&mock_server)
+            .await;
+
+        let snapshots = snapshots(&mock_server, 1, 10);
+        let first = snapshots.current().await.unwrap();
+
+        sleep(Duration::from_millis(1100)).await;
+        let served = snapshots.current().await.unwrap();
+        assert_eq!(served.id, first.id);
+        assert_eq!(served.servers, first.servers);
+        assert!(!served.remaining_ttl().is_zero());
+    }
+
+    #[tokio::test]
+    async fn test_failed_rebuild_is_not_retried_by_every_request() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/get_server_list.php"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(format!("<result>{}</result>", server("A", 1, 5))),
+            )
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/get_server_list.php"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&mock_server)
+            .await;
+
+        let snapshots = snapshots(&mock_server, 1, 10);
+        snapshots.current().await.unwrap();
+        sleep(Duration::from_millis(1100)).await;
+
+        // The first request during the outage tries to rebuild, the next one
+        // is served the previous snapshot without going upstream
+        snapshots.current().await.unwrap();
+        let requests = mock_server.received_requests().await.unwrap().len();
+        assert!(requests > 1);
+        snapshots.current().await.unwrap();
+        assert_eq!(
+            mock_server.received_requests().await.unwrap().len(),
+            requests
+        );
+    }
+
+    #[tokio::test]
+    async fn test_previous_snapshot_served_during_rebuild() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/get_server_list.php"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(format!("<result>{}</result>", server("A", 1, 5))),
+            )
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/get_server_list.php"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string(format!("<result>{}</result>", server("B", 2, 5)))
+                    .set_delay(Duration::from_millis(500)),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let snapshots = Arc::new(snapshots(&mock_server, 1, 10));
+        let first = snapshots.current().await.unwrap();
+        sleep(Duration::from_millis(1100)).await;
+
+        let rebuild = tokio::spawn({
+            let snapshots = snapshots.clone();
+            async move { snapshots.current().await.unwrap() }
+        });
+        sleep(Duration::from_millis(100)).await;
+
+        // Served immediately while the rebuild waits for the upstream
+        let started = std::time::Instant::now();
+        let served = snapshots.current().await.unwrap();
+        assert!(started.elapsed() < Duration::from_millis(200));
+        assert!(Arc::ptr_eq(&served, &first));
+
+        let rebuilt = rebuild.await.unwrap();
+        assert_eq!(rebuilt.servers[0].name, "B");
+        assert!(Arc::ptr_eq(&snapshots.current().await.unwrap(), &rebuilt));
+    }
+
+    #[tokio::test]
+    async fn test_error_without_previous_snapshot() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/get_server_list.php"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&mock_server)
+            .await;
+
+        let err = snapshots(&mock_server, 10, 10).current().await.unwrap_err();
+        assert!(err.contains("status 503"));
+    }
+}
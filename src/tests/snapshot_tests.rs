@@ -0,0 +1,264 @@
+#[cfg(test)]
+mod tests {
+    use crate::snapshot::SNAPSHOT_VERSION;
+    use crate::tests::helpers::cache_without_retries;
+    use crate::{CachePolicy, CacheSnapshot, CacheStatus, ContentEncoding, SnapshotEntry};
+    use std::path::PathBuf;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+    use tokio::sync::watch;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{method, path},
+    };
+
+    /// Unique snapshot path in the system temp directory.
+    fn snapshot_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "rwrs-snapshot-{}-{}.json",
+            std::process::id(),
+            name
+        ));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn entry(key: &str, data: &str, age: Duration) -> SnapshotEntry {
+        let fetched_at = SystemTime::now() - age;
+        SnapshotEntry {
+            key: key.to_string(),
+            data: data.to_string(),
+            status_code: 200,
+            fetched_at_ms: fetched_at.duration_since(UNIX_EPOCH).unwrap().as_millis() as u64,
+            ttl_ms: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_round_trip_keeps_age() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/list"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("list_data"))
+            .mount(&mock_server)
+            .await;
+        let url = format!("{}/list", mock_server.uri());
+
+        let cache = cache_without_retries(10);
+        cache.get_cached_response(&url).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let path = snapshot_path("round-trip");
+        assert_eq!(cache.save_snapshot(&path).await.unwrap(), 1);
+
+        let restarted = cache_without_retries(10).with_stale_window(30);
+        assert_eq!(restarted.load_snapshot(&path).await.unwrap(), 1);
+
+        let lookup = restarted.lookup(&url).await.unwrap();
+        assert_eq!(lookup.status, CacheStatus::Stale);
+        assert_eq!(lookup.data, "list_data");
+        assert!(lookup.age >= Duration::from_millis(200));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_restored_entry_is_stale_and_revalidated() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/list"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("new_data"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        let url = format!("{}/list", mock_server.uri());
+
+        let cache = cache_without_retries(10).with_stale_window(30);
+        let restored = cache
+            .restore(CacheSnapshot::new(vec![entry(
+                &url,
+                "old_data",
+                Duration::from_secs(1),
+            )]))
+            .await;
+        assert_eq!(restored, 1);
+
+        // Still within its TTL, but served as stale while it is refreshed
+        let lookup = cache.lookup(&url).await.unwrap();
+        assert_eq!(lookup.status, CacheStatus::Stale);
+        assert_eq!(lookup.data, "old_data");
+        assert!(lookup.age >= Duration::from_secs(1));
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let lookup = cache.lookup(&url).await.unwrap();
+        assert_eq!(lookup.status, CacheStatus::Hit);
+        assert_eq!(lookup.data, "new_data");
+    }
+
+    #[tokio::test]
+    async fn test_restored_entry_served_when_upstream_fails() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/list"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&mock_server)
+            .await;
+        let url = format!("{}/list", mock_server.uri());
+
+        let cache = cache_without_retries(3)
+            .with_stale_if_error(60)
+            .with_default_policy(CachePolicy::new(3, 1));
+        cache
+            .restore(CacheSnapshot::new(vec![entry(
+                &url,
+                "old_data",
+                Duration::from_secs(20),
+            )]))
+            .await;
+
+        let lookup = cache.lookup(&url).await.unwrap();
+        assert_eq!(lookup.status, CacheStatus::StaleIfError);
+        assert_eq!(lookup.data, "old_data");
+    }
+
+    #[tokio::test]
+    async fn test_restored_entry_is_compressed() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/list"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&mock_server)
+            .await;
+        let url = format!("{}/list", mock_server.uri());
+        let body = "<server><name>Test</name></server>".repeat(100);
+
+        let cache = cache_without_retries(3)
+            .with_stale_if_error(60)
+            .with_compression(true);
+        cache
+            .restore(CacheSnapshot::new(vec![entry(
+                &url,
+                &body,
+                Duration::from_secs(20),
+            )]))
+            .await;
+
+        let lookup = cache.lookup(&url).await.unwrap();
+        assert_eq!(lookup.data, body.as_str());
+        assert!(lookup.encodings.get(ContentEncoding::Gzip).is_some());
+        assert!(lookup.encodings.get(ContentEncoding::Brotli).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_restore_skips_unservable_entries() {
+        let cache = cache_without_retries(3).with_stale_if_error(60);
+        let mut error_entry = entry("error", "", Duration::ZERO);
+        error_entry.status_code = 500;
+
+        let restored = cache
+            .restore(CacheSnapshot::new(vec![
+                entry("recent", "data", Duration::from_secs(10)),
+                entry("too_old", "data", Duration::from_secs(120)),
+                error_entry,
+            ]))
+            .await;
+
+        assert_eq!(restored, 1);
+        assert_eq!(cache.len().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_restore_respects_entry_limit() {
+        let cache = cache_without_retries(10).with_limits(2, 0);
+        let restored = cache
+            .restore(CacheSnapshot::new(vec![
+                entry("a", "data", Duration::ZERO),
+                entry("b", "data", Duration::ZERO),
+                entry("c", "data", Duration::ZERO),
+            ]))
+            .await;
+
+        assert_eq!(restored, 3);
+        // The least recently used entry, first in the snapshot, is evicted
+        let keys: Vec<String> = cache
+            .snapshot()
+            .await
+            .unwrap()
+            .entries
+            .into_iter()
+            .map(|entry| entry.key)
+            .collect();
+        assert_eq!(keys, vec!["b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_excludes_errors() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/missing"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&mock_server)
+            .await;
+
+        let cache = cache_without_retries(10).with_default_policy(CachePolicy::new(10, 10));
+        cache
+            .get_cached_response(&format!("{}/missing", mock_server.uri()))
+            .await
+            .unwrap();
+        assert_eq!(cache.len().await, 1);
+
+        assert!(cache.snapshot().await.unwrap().entries.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_load_missing_snapshot() {
+        let cache = cache_without_retries(10);
+        let path = snapshot_path("missing");
+        assert_eq!(cache.load_snapshot(&path).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_load_invalid_snapshot() {
+        let cache = cache_without_retries(10);
+
+        let path = snapshot_path("invalid");
+        std::fs::write(&path, "not json").unwrap();
+        assert!(cache.load_snapshot(&path).await.is_err());
+
+        let snapshot = CacheSnapshot {
+            version: SNAPSHOT_VERSION + 1,
+            saved_at_ms: 0,
+            entries: vec![],
+        };
+        std::fs::write(&path, serde_json::to_string(&snapshot).unwrap()).unwrap();
+        let error = cache.load_snapshot(&path).await.unwrap_err();
+        assert!(error.contains("Unsupported cache snapshot version"));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_snapshotter_saves_on_shutdown() {
+        let cache = cache_without_retries(10);
+        cache
+            .restore(CacheSnapshot::new(vec![entry(
+                "key",
+                "data",
+                Duration::ZERO,
+            )]))
+            .await;
+
+        let path = snapshot_path("shutdown");
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let handle = cache.spawn_snapshotter(path.clone(), Duration::ZERO, shutdown_rx);
+        assert!(!path.exists());
+
+        shutdown_tx.send(true).unwrap();
+        tokio::time::timeout(Duration::from_secs(1), handle)
+            .await
+            .expect("snapshotter did not stop")
+            .unwrap();
+
+        let snapshot = CacheSnapshot::read_from(&path).await.unwrap().unwrap();
+        assert_eq!(snapshot.entries.len(), 1);
+        assert_eq!(snapshot.entries[0].data, "data");
+        let _ = std::fs::remove_file(&path);
+    }
+}
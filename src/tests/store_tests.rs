@@ -0,0 +1,406 @@
+#[cfg(test)]
+mod tests {
+    use crate::tests::helpers::cache_without_retries;
+    use crate::{
+        ApiCache, CacheBackend, CacheStatus, CacheStore, CachedResponse, DiskStore, MemoryStore,
+        RedisStore,
+    };
+    use std::sync::Arc;
+    use std::time::Duration;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{method, path},
+    };
+
+    fn response(body: &str) -> CachedResponse {
+        CachedResponse::new(&Ok((body.into(), 200)), Duration::from_secs(10))
+    }
+
+    /// Put, read, list and remove an entry, as every store must support.
+    async fn check_round_trip(store: &dyn CacheStore) {
+        store
+            .put(
+                "server_list?start=0",
+                response("page 0"),
+                Duration::from_secs(60),
+            )
+            .await
+            .unwrap();
+
+        let entry = store.get("server_list?start=0").await.unwrap().unwrap();
+        assert_eq!(entry.data, "page 0");
+        assert_eq!(entry.status_code, 200);
+        assert!(store.get("server_list?start=100").await.unwrap().is_none());
+
+        let stats = store.stats().await.unwrap();
+        assert_eq!(stats.entries, 1);
+        assert!(stats.size_bytes >= "server_list?start=0page 0".len());
+
+        let entries = store.entries().await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].0, "server_list?start=0");
+
+        assert!(store.remove("server_list?start=0").await.unwrap());
+        assert!(!store.remove("server_list?start=0").await.unwrap());
+        assert!(store.get("server_list?start=0").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_round_trip() {
+        check_round_trip(&MemoryStore::default()).await;
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_sweeps_after_retention() {
+        let store = MemoryStore::default();
+        store
+            .put("short", response("data"), Duration::from_millis(100))
+            .await
+            .unwrap();
+        store
+            .put("long", response("data"), Duration::from_secs(60))
+            .await
+            .unwrap();
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(store.sweep_expired().await.unwrap(), 1);
+        assert!(store.get("short").await.unwrap().is_none());
+        assert!(store.get("long").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_lists_least_recently_used_first() {
+        let store = MemoryStore::default();
+        for key in ["a", "b", "c"] {
+            store
+                .put(key, response("data"), Duration::from_secs(60))
+                .await
+                .unwrap();
+        }
+        store.get("a").await.unwrap();
+
+        let keys: Vec<String> = store
+            .entries()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect();
+        assert_eq!(keys, vec!["b", "c", "a"]);
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_evicts_across_shards() {
+        let store = MemoryStore::new(10, 0);
+        for i in 0..50 {
+            store
+                .put(
+                    &format!("key{}", i),
+                    response("data"),
+                    Duration::from_secs(60),
+                )
+                .await
+                .unwrap();
+        }
+
+        let keys: Vec<String> = store
+            .entries()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect();
+        let expected: Vec<String> = (40..50).map(|i| format!("key{}", i)).collect();
+        assert_eq!(keys, expected);
+        assert_eq!(store.stats().await.unwrap().entries, 10);
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_concurrent_puts_keep_totals() {
+        let store = Arc::new(MemoryStore::default());
+        let tasks: Vec<_> = (0..8)
+            .map(|task| {
+                let store = store.clone();
+                tokio::spawn(async move {
+                    for i in 0..100 {
+                        let key = format!("task{}:{}", task, i);
+                        store
+                            .put(&key, response("data"), Duration::from_secs(60))
+                            .await
+                            .unwrap();
+                        // Replacing an entry must not count it twice
+                        store
+                            .put(&key, response("data"), Duration::from_secs(60))
+                            .await
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        let entries = store.entries().await.unwrap();
+        let size_bytes: usize = entries
+            .iter()
+            .map(|(key, entry)| entry.size_bytes(key))
+            .sum();
+        let stats = store.stats().await.unwrap();
+        assert_eq!(stats.entries, 800);
+        assert_eq!(entries.len(), 800);
+        assert_eq!(stats.size_bytes, size_bytes);
+
+        for (key, _) in entries.iter().take(300) {
+            assert!(store.remove(key).await.unwrap());
+        }
+        assert_eq!(store.stats().await.unwrap().entries, 500);
+    }
+
+    #[tokio::test]
+    async fn test_disk_store_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        check_round_trip(&DiskStore::new(dir.path().join("cache"))).await;
+    }
+
+    #[tokio::test]
+    async fn test_disk_store_missing_directory_is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = DiskStore::new(dir.path().join("missing"));
+
+        assert!(store.get("key").await.unwrap().is_none());
+        assert!(store.entries().await.unwrap().is_empty());
+        assert_eq!(store.stats().await.unwrap().entries, 0);
+        assert_eq!(store.sweep_expired().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_disk_store_drops_entries_after_retention() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = DiskStore::new(dir.path());
+        store
+            .put("short", response("data"), Duration::from_millis(100))
+            .await
+            .unwrap();
+        store
+            .put("other", response("data"), Duration::from_millis(100))
+            .await
+            .unwrap();
+        store
+            .put("long", response("data"), Duration::from_secs(60))
+            .await
+            .unwrap();
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(store.get("short").await.unwrap().is_none());
+        assert_eq!(store.stats().await.unwrap().entries, 1);
+        // "short" was already removed when it was read
+        assert_eq!(store.sweep_expired().await.unwrap(), 1);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_disk_store_names_files_after_key_hash() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = DiskStore::new(dir.path());
+        for key in ["a", "b"] {
+            store
+                .put(key, response(key), Duration::from_secs(60))
+                .await
+                .unwrap();
+        }
+
+        let mut names: Vec<String> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                // SHA-256 of "b" and "a"
+                "3e23e8160039594a33894f6564e1b1348bbd7a0088d42c4acb73eeaed59c009d.json",
+                "ca978112ca1bbdcafac231b39a23dc4da786eff8147c4e72b9807785afee48bb.json",
+            ]
+        );
+        assert_eq!(store.get("a").await.unwrap().unwrap().data, "a");
+        assert_eq!(store.get("b").await.unwrap().unwrap().data, "b");
+    }
+
+    #[tokio::test]
+    async fn test_disk_store_stats_read_only_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = DiskStore::new(dir.path());
+        store
+            .put("key", response("data"), Duration::from_secs(60))
+            .await
+            .unwrap();
+
+        // The file's modification time is the entry's expiry
+        let file = std::fs::read_dir(dir.path())
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        let metadata = file.metadata().unwrap();
+        let expires_in = metadata
+            .modified()
+            .unwrap()
+            .duration_since(std::time::SystemTime::now())
+            .unwrap();
+        assert!(expires_in > Duration::from_secs(55));
+
+        let stats = store.stats().await.unwrap();
+        assert_eq!(stats.entries, 1);
+        assert_eq!(stats.size_bytes, metadata.len() as usize);
+    }
+
+    #[tokio::test]
+    async fn test_disk_store_skips_unreadable_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = DiskStore::new(dir.path());
+        store
+            .put("key", response("data"), Duration::from_secs(60))
+            .await
+            .unwrap();
+        std::fs::write(dir.path().join("garbage.json"), "not json").unwrap();
+
+        assert_eq!(store.entries().await.unwrap().len(), 1);
+        assert_eq!(store.stats().await.unwrap().entries, 1);
+    }
+
+    #[tokio::test]
+    async fn test_caches_share_disk_store() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/list"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("list_data"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        let url = format!("{}/list", mock_server.uri());
+
+        let dir = tempfile::tempdir().unwrap();
+        let first = cache_without_retries(10).with_store(Arc::new(DiskStore::new(dir.path())));
+        let second = cache_without_retries(10).with_store(Arc::new(DiskStore::new(dir.path())));
+
+        let lookup = first.lookup(&url).await.unwrap();
+        assert_eq!(lookup.status, CacheStatus::Miss);
+
+        // The second replica is served from the entry stored by the first
+        let lookup = second.lookup(&url).await.unwrap();
+        assert_eq!(lookup.status, CacheStatus::Hit);
+        assert_eq!(lookup.data, "list_data");
+        assert_eq!(second.len().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_hits_counted_with_disk_store() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/hits"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("hits_data"))
+            .expect(3)
+            .mount(&mock_server)
+            .await;
+        let url = format!("{}/hits", mock_server.uri());
+
+        let dir = tempfile::tempdir().unwrap();
+        // Retained past its TTL, so the expired entry is refreshed in place
+        let cache = ApiCache::new(1)
+            .with_stale_if_error(60)
+            .with_store(Arc::new(DiskStore::new(dir.path())));
+        for _ in 0..3 {
+            cache.lookup(&url).await.unwrap();
+        }
+        let entries = cache.entries("").await.unwrap();
+        assert_eq!(entries[0].hits, 2);
+        assert!(entries[0].last_hit_secs.is_some());
+
+        // Kept when the entry is refreshed, dropped when it is purged
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+        cache.lookup(&url).await.unwrap();
+        assert_eq!(cache.entries("").await.unwrap()[0].hits, 2);
+        cache.lookup(&url).await.unwrap();
+        assert_eq!(cache.entries("").await.unwrap()[0].hits, 3);
+
+        assert!(cache.purge(&url).await.unwrap());
+        cache.lookup(&url).await.ok();
+        assert!(cache.entries("").await.unwrap().iter().all(|e| e.hits == 0));
+    }
+
+    #[test]
+    fn test_redis_store_rejects_invalid_url() {
+        assert!(RedisStore::new("not a url", "rwrs:").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_redis_store_backs_off_after_failed_connect() {
+        let store = RedisStore::new("redis://127.0.0.1:1", "rwrs:").unwrap();
+
+        let error = store.get("key").await.unwrap_err();
+        assert!(error.starts_with("Failed to connect to Redis"));
+        // Skipped without another connect until the backoff has passed
+        let error = store.get("key").await.unwrap_err();
+        assert_eq!(error, "Redis is unavailable, not reconnecting yet");
+    }
+
+    #[test]
+    fn test_cache_backend_from_str() {
+        assert_eq!("memory".parse(), Ok(CacheBackend::Memory));
+        assert_eq!("Redis".parse(), Ok(CacheBackend::Redis));
+        assert_eq!(" disk ".parse(), Ok(CacheBackend::Disk));
+        assert!("memcached".parse::<CacheBackend>().is_err());
+    }
+
+    /// Redis store under a unique prefix on the server in `CACHE_REDIS_URL`.
+    fn redis_store() -> RedisStore {
+        let url = std::env::var("CACHE_REDIS_URL")
+            .unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string());
+        let prefix = format!("rwrs-test:{}:{}:", std::process::id(), fastrand::u64(..));
+        RedisStore::new(&url, &prefix).unwrap()
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a local redis-server"]
+    async fn test_redis_store_round_trip() {
+        check_round_trip(&redis_store()).await;
+    }
+
+    #[tokio::test]
+    #[ignore = "requires a local redis-server"]
+    async fn test_redis_store_expires_entries() {
+        let store = redis_store();
+        store
+            .put("key", response("data"), Duration::from_millis(100))
+            .await
+            .unwrap();
+        assert!(store.get("key").await.unwrap().is_some());
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert!(store.get("key").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_redis_store_unreachable_is_a_miss() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/list"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("list_data"))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+        let url = format!("{}/list", mock_server.uri());
+
+        // Nothing listens on port 1, so every store request fails
+        let store = RedisStore::new("redis://127.0.0.1:1", "rwrs:").unwrap();
+        let cache = cache_without_retries(10).with_store(Arc::new(store));
+
+        for _ in 0..2 {
+            let lookup = cache.lookup(&url).await.unwrap();
+            assert_eq!(lookup.status, CacheStatus::Miss);
+            assert_eq!(lookup.data, "list_data");
+        }
+        assert_eq!(cache.len().await, 0);
+    }
+}
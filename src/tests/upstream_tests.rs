@@ -0,0 +1,181 @@
+#[cfg(test)]
+mod tests {
+    use crate::{ApiCache, CachePolicy, UpstreamClient, UpstreamConfig, get_latest_tag};
+    use std::time::{Duration, Instant};
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{header, method, path},
+    };
+
+    /// Client with fast retries so tests do not wait long
+    fn test_client(max_retries: u32) -> UpstreamClient {
+        UpstreamClient::new(UpstreamConfig {
+            max_retries,
+            retry_backoff: Duration::from_millis(10),
+            ..UpstreamConfig::default()
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_get_text_success() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/list"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("list_data"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = test_client(2);
+        let (body, status) = client
+            .get_text(&format!("{}/list", mock_server.uri()))
+            .await
+            .unwrap();
+        assert_eq!(body, "list_data");
+        assert_eq!(status, 200);
+    }
+
+    #[tokio::test]
+    async fn test_server_errors_are_retried() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/retry"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/retry"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("recovered"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = test_client(2);
+        let (body, status) = client
+            .get_text(&format!("{}/retry", mock_server.uri()))
+            .await
+            .unwrap();
+        assert_eq!(body, "recovered");
+        assert_eq!(status, 200);
+    }
+
+    #[tokio::test]
+    async fn test_last_outcome_returned_when_retries_exhausted() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/down"))
+            .respond_with(ResponseTemplate::new(500).set_body_string("still down"))
+            .expect(3)
+            .mount(&mock_server)
+            .await;
+
+        let client = test_client(2);
+        let (body, status) = client
+            .get_text(&format!("{}/down", mock_server.uri()))
+            .await
+            .unwrap();
+        assert_eq!(body, "still down");
+        assert_eq!(status, 500);
+    }
+
+    #[tokio::test]
+    async fn test_client_errors_are_not_retried() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/missing"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = test_client(2);
+        let (_, status) = client
+            .get_text(&format!("{}/missing", mock_server.uri()))
+            .await
+            .unwrap();
+        assert_eq!(status, 404);
+    }
+
+    #[tokio::test]
+    async fn test_transport_errors_are_retried_with_backoff() {
+        let client = UpstreamClient::new(UpstreamConfig {
+            max_retries: 2,
+            retry_backoff: Duration::from_millis(100),
+            ..UpstreamConfig::default()
+        })
+        .unwrap();
+
+        let started = Instant::now();
+        let result = client.get_text("http://127.0.0.1:1/unreachable").await;
+        assert!(result.unwrap_err().contains("Request failed"));
+        // Two retries wait at least 50ms and 100ms (half of each delay is fixed)
+        assert!(started.elapsed() >= Duration::from_millis(150));
+    }
+
+    #[tokio::test]
+    async fn test_response_size_limit() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/large"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("x".repeat(2048)))
+            .mount(&mock_server)
+            .await;
+
+        let client = UpstreamClient::new(UpstreamConfig {
+            max_retries: 0,
+            max_response_bytes: 1024,
+            ..UpstreamConfig::default()
+        })
+        .unwrap();
+        let result = client
+            .get_text(&format!("{}/large", mock_server.uri()))
+            .await;
+        assert!(result.unwrap_err().contains("Response too large"));
+    }
+
+    #[tokio::test]
+    async fn test_user_agent_is_sent() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/ua"))
+            .and(header("user-agent", "rwrs-test-agent"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("ok"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = UpstreamClient::new(UpstreamConfig {
+            user_agent: "rwrs-test-agent".to_string(),
+            ..UpstreamConfig::default()
+        })
+        .unwrap();
+        let (_, status) = client
+            .get_text(&format!("{}/ua", mock_server.uri()))
+            .await
+            .unwrap();
+        assert_eq!(status, 200);
+    }
+
+    #[tokio::test]
+    async fn test_get_latest_tag_rejects_non_github_url() {
+        let cache = ApiCache::new(10).with_client(test_client(0));
+        assert!(
+            get_latest_tag(
+                &cache,
+                "https://gitlab.com/owner/repo",
+                CachePolicy::new(300, 60)
+            )
+            .await
+            .is_none()
+        );
+    }
+}
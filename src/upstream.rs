@@ -0,0 +1,187 @@
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+use crate::{RateLimiter, UpstreamPermit};
+
+/// Settings for the shared upstream HTTP client.
+#[derive(Debug, Clone)]
+pub struct UpstreamConfig {
+    pub connect_timeout: Duration,
+    /// Maximum time to wait for the next chunk of a response.
+    pub read_timeout: Duration,
+    /// Maximum time for a whole request, including the body.
+    pub request_timeout: Duration,
+    pub user_agent: String,
+    /// Additional attempts made after a failed request.
+    pub max_retries: u32,
+    /// Base delay before the first retry, doubled for every further attempt.
+    pub retry_backoff: Duration,
+    /// Responses larger than this are rejected. Zero disables the limit.
+    pub max_response_bytes: usize,
+}
+
+impl Default for UpstreamConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(5),
+            read_timeout: Duration::from_secs(10),
+            request_timeout: Duration::from_secs(10),
+            user_agent: format!("rwrs-server/{}", env!("CARGO_PKG_VERSION")),
+            max_retries: 2,
+            retry_backoff: Duration::from_millis(200),
+            max_response_bytes: 8 * 1024 * 1024,
+        }
+    }
+}
+
+/// HTTP client shared by every upstream request, so connections and TLS
+/// sessions are pooled across requests.
+#[derive(Debug, Clone)]
+pub struct UpstreamClient {
+    client: reqwest::Client,
+    config: UpstreamConfig,
+}
+
+impl UpstreamClient {
+    pub fn new(config: UpstreamConfig) -> Result<Self, String> {
+        let client = reqwest::Client::builder()
+            .connect_timeout(config.connect_timeout)
+            .read_timeout(config.read_timeout)
+            .timeout(config.request_timeout)
+            .user_agent(config.user_agent.clone())
+            .build()
+            .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+
+        Ok(Self { client, config })
+    }
+
+    /// GET `url` and return its body and status code.
+    ///
+    /// Failed requests and 5xx responses are retried with jittered
+    /// exponential backoff; the last outcome is returned once all attempts
+    /// are used up.
+    pub async fn get_text(&self, url: &str) -> Result<(String, u16), String> {
+        self.get_text_with(url, None, None, self.config.max_retries)
+            .await
+    }
+
+    /// Like [`UpstreamClient::get_text`], but every retry first takes a
+    /// permit from `limiter`, so each request sent counts against the
+    /// upstream budget. `permit` covers the first attempt. When a retry is
+    /// rejected, the outcome of the previous attempt is returned. Without
+    /// `retry` the request is sent once, so a caller with another upstream
+    /// to fail over to does not wait out the backoff first.
+    pub async fn get_text_limited(
+        &self,
+        url: &str,
+        limiter: &RateLimiter,
+        permit: UpstreamPermit,
+        retry: bool,
+    ) -> Result<(String, u16), String> {
+        let max_retries = if retry { self.config.max_retries } else { 0 };
+        self.get_text_with(url, Some(limiter), Some(permit), max_retries)
+            .await
+    }
+
+    async fn get_text_with(
+        &self,
+        url: &str,
+        limiter: Option<&RateLimiter>,
+        mut permit: Option<UpstreamPermit>,
+        max_retries: u32,
+    ) -> Result<(String, u16), String> {
+        let mut attempt = 0;
+        loop {
+            let result = self.get_text_once(url).await;
+            // No concurrency slot is held while waiting to retry
+            drop(permit.take());
+            let retryable = match &result {
+                Ok((_, status_code)) => *status_code >= 500,
+                Err(_) => true,
+            };
+            if !retryable || attempt >= max_retries {
+                return result;
+            }
+
+            let delay = self.backoff_delay(attempt);
+            attempt += 1;
+            warn!(
+                "Request to {} failed, retrying in {:?} (attempt {} of {})",
+                url, delay, attempt, max_retries
+            );
+            tokio::time::sleep(delay).await;
+
+            if let Some(limiter) = limiter {
+                match limiter.acquire().await {
+                    Ok(next) => permit = Some(next),
+                    Err(e) => {
+                        warn!("Not retrying request to {}: {}", url, e);
+                        return result;
+                    }
+                }
+            }
+        }
+    }
+
+    async fn get_text_once(&self, url: &str) -> Result<(String, u16), String> {
+        info!("Making request to external API: {}", url);
+        let mut response = match self.client.get(url).send().await {
+            Ok(response) => response,
+            Err(e) => {
+                error!("Request failed: {}", e);
+                return Err(format!("Request failed: {}", e));
+            }
+        };
+
+        let status = response.status().as_u16();
+        info!("Received response with status: {}", status);
+
+        let limit = self.config.max_response_bytes;
+        if limit > 0
+            && let Some(length) = response.content_length()
+            && length > limit as u64
+        {
+            error!("Response from {} is too large: {} bytes", url, length);
+            return Err(format!(
+                "Response too large: {} bytes exceeds limit of {} bytes",
+                length, limit
+            ));
+        }
+
+        // Read chunk by chunk so bodies without a Content-Length are limited too
+        let mut body = Vec::new();
+        loop {
+            match response.chunk().await {
+                Ok(Some(chunk)) => {
+                    body.extend_from_slice(&chunk);
+                    if limit > 0 && body.len() > limit {
+                        error!("Response from {} exceeded {} bytes", url, limit);
+                        return Err(format!(
+                            "Response too large: exceeds limit of {} bytes",
+                            limit
+                        ));
+                    }
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    error!("Failed to read response body: {}", e);
+                    return Err(format!("Failed to read response body: {}", e));
+                }
+            }
+        }
+
+        info!("Successfully fetched {} bytes from API", body.len());
+        Ok((String::from_utf8_lossy(&body).into_owned(), status))
+    }
+
+    /// Exponential backoff with jitter: half of the delay is fixed, the other
+    /// half random, so retries from many requests do not line up.
+    fn backoff_delay(&self, attempt: u32) -> Duration {
+        let delay = self
+            .config
+            .retry_backoff
+            .saturating_mul(2u32.saturating_pow(attempt));
+        let half = delay / 2;
+        half + half.mul_f64(fastrand::f64())
+    }
+}